@@ -45,11 +45,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // wait enough for the go commands to complete in the background
     tokio::time::sleep(tokio::time::Duration::from_millis(20000)).await;
 
-    // quit engine
-    engine.quit();
-
-    // wait for engine to quit gracefully
-    tokio::time::sleep(tokio::time::Duration::from_millis(3000)).await;
+    // quit engine and wait for it to exit
+    engine.shutdown().await;
 
     Ok(())
 }
@@ -21,7 +21,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let engine = UciEngine::new("stockfish12.exe");
 
     // start engine detached
-    let _ = engine.go(go_job);
+    drop(engine.go(go_job));
 
     // do something in the meanwhile
     println!("doing something");
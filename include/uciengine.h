@@ -0,0 +1,171 @@
+#ifndef UCIENGINE_H
+#define UCIENGINE_H
+
+/* generated by cbindgen from the ffi module, regenerate with: cbindgen --output include/uciengine.h */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * analysis info
+ */
+typedef struct AnalysisInfo AnalysisInfo;
+
+/**
+ * go command job
+ */
+typedef struct GoJob GoJob;
+
+/**
+ * engine handle owning the runtime the engine tasks run on
+ */
+typedef struct UciEngineHandle UciEngineHandle;
+
+/**
+ * free a string returned by this library
+ *
+ * # Safety
+ *
+ * `s` must be null or a string returned by this library that was not freed yet
+ */
+void uciengine_string_free(char *s);
+
+/**
+ * create new analysis info
+ */
+struct AnalysisInfo *uciengine_analysis_info_new(void);
+
+/**
+ * free analysis info
+ *
+ * # Safety
+ *
+ * `ai` must be null or a pointer returned by `uciengine_analysis_info_new` that was not freed yet
+ */
+void uciengine_analysis_info_free(struct AnalysisInfo *ai);
+
+/**
+ * parse info line into analysis info, returns 0 on success, -1 on failure
+ *
+ * # Safety
+ *
+ * `ai` must be a valid analysis info pointer, `line` a nul terminated string
+ */
+int uciengine_analysis_info_parse(struct AnalysisInfo *ai, const char *line);
+
+/**
+ * analysis info as json, null on failure
+ *
+ * # Safety
+ *
+ * `ai` must be a valid analysis info pointer
+ */
+char *uciengine_analysis_info_to_json(const struct AnalysisInfo *ai);
+
+/**
+ * create new go job
+ */
+struct GoJob *uciengine_go_job_new(void);
+
+/**
+ * free go job that was not passed to `uciengine_engine_go`
+ *
+ * # Safety
+ *
+ * `job` must be null or a pointer returned by `uciengine_go_job_new` that was not freed or consumed yet
+ */
+void uciengine_go_job_free(struct GoJob *job);
+
+/**
+ * set position startpos, returns 0 on success, -1 on failure
+ *
+ * # Safety
+ *
+ * `job` must be a valid go job pointer
+ */
+int uciengine_go_job_pos_startpos(struct GoJob *job);
+
+/**
+ * set position fen, returns 0 on success, -1 on failure
+ *
+ * # Safety
+ *
+ * `job` must be a valid go job pointer, `fen` a nul terminated string
+ */
+int uciengine_go_job_pos_fen(struct GoJob *job, const char *fen);
+
+/**
+ * set position moves ( space separated uci moves ), returns 0 on success, -1 on failure
+ *
+ * # Safety
+ *
+ * `job` must be a valid go job pointer, `moves` a nul terminated string
+ */
+int uciengine_go_job_pos_moves(struct GoJob *job, const char *moves);
+
+/**
+ * set uci option, returns 0 on success, -1 on failure
+ *
+ * # Safety
+ *
+ * `job` must be a valid go job pointer, `key` and `value` nul terminated strings
+ */
+int uciengine_go_job_uci_opt(struct GoJob *job, const char *key, const char *value);
+
+/**
+ * set go option, returns 0 on success, -1 on failure
+ *
+ * # Safety
+ *
+ * `job` must be a valid go job pointer, `key` and `value` nul terminated strings
+ */
+int uciengine_go_job_go_opt(struct GoJob *job, const char *key, const char *value);
+
+/**
+ * spawn engine, null on failure
+ *
+ * # Safety
+ *
+ * `path` must be a nul terminated string
+ */
+struct UciEngineHandle *uciengine_engine_new(const char *path);
+
+/**
+ * issue go job, the job is consumed, returns 0 on success, -1 on failure
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine handle, `job` a pointer returned by `uciengine_go_job_new`
+ */
+int uciengine_engine_go(struct UciEngineHandle *engine, struct GoJob *job);
+
+/**
+ * latest analysis info as json, null if nothing new arrived since the last poll
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine handle
+ */
+char *uciengine_engine_poll_info(struct UciEngineHandle *engine);
+
+/**
+ * result of the oldest pending go job as json, null if it is not done yet
+ *
+ * # Safety
+ *
+ * `engine` must be a valid engine handle
+ */
+char *uciengine_engine_poll_result(struct UciEngineHandle *engine);
+
+/**
+ * quit engine and free engine handle
+ *
+ * # Safety
+ *
+ * `engine` must be null or a pointer returned by `uciengine_engine_new` that was not freed yet
+ */
+void uciengine_engine_free(struct UciEngineHandle *engine);
+
+#endif  /* UCIENGINE_H */
@@ -0,0 +1,100 @@
+use crate::analysis::*;
+#[cfg(test)]
+use crate::prelude::*;
+
+/// strategy used when merging consecutive info lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// every info line overwrites the fields it carries
+    LastWriteWins,
+    /// info lines reporting a shallower depth than the accumulated one are discarded
+    DeepestWins,
+}
+
+/// accumulates info lines into a single analysis info
+#[derive(Debug, Clone, Copy)]
+pub struct InfoAccumulator {
+    /// merge strategy
+    strategy: MergeStrategy,
+    /// accumulated analysis info
+    ai: AnalysisInfo,
+}
+
+/// info accumulator implementation
+impl InfoAccumulator {
+    /// create new info accumulator with given merge strategy
+    pub fn new(strategy: MergeStrategy) -> Self {
+        Self {
+            strategy,
+            ai: AnalysisInfo::new(),
+        }
+    }
+
+    /// get merge strategy
+    pub fn strategy(&self) -> MergeStrategy {
+        self.strategy
+    }
+
+    /// get accumulated analysis info
+    pub fn ai(&self) -> AnalysisInfo {
+        self.ai
+    }
+
+    /// reset accumulated analysis info, keeping the merge strategy
+    pub fn reset(&mut self) {
+        self.ai = AnalysisInfo::new();
+    }
+
+    /// push an engine output line, returns whether the line was merged
+    ///
+    /// lines that fail to parse leave the accumulated info untouched
+    pub fn push<T: AsRef<str>>(&mut self, line: T) -> Result<bool, InfoParseError> {
+        let mut candidate = self.ai;
+
+        candidate.parse(line)?;
+
+        if let MergeStrategy::DeepestWins = self.strategy {
+            if candidate.depth < self.ai.depth {
+                return Ok(false);
+            }
+        }
+
+        self.ai = candidate;
+
+        Ok(true)
+    }
+}
+
+#[test]
+fn last_write_wins() {
+    let mut acc = InfoAccumulator::new(MergeStrategy::LastWriteWins);
+
+    assert!(acc.push("info depth 10 score cp 30 pv e2e4").unwrap());
+    assert!(acc.push("info depth 8 score cp 12 pv d2d4").unwrap());
+
+    assert_eq!(acc.ai().depth, 8);
+    assert_eq!(acc.ai().bestmove(), Some("d2d4".to_string()));
+}
+
+#[test]
+fn deepest_wins() {
+    let mut acc = InfoAccumulator::new(MergeStrategy::DeepestWins);
+
+    assert!(acc.push("info depth 10 score cp 30 pv e2e4").unwrap());
+    assert!(!acc.push("info depth 8 score cp 12 pv d2d4").unwrap());
+    assert!(acc.push("info nodes 5000 nps 1000").unwrap());
+
+    assert_eq!(acc.ai().depth, 10);
+    assert_eq!(acc.ai().nodes, 5000);
+    assert_eq!(acc.ai().bestmove(), Some("e2e4".to_string()));
+}
+
+#[test]
+fn failed_line_leaves_info_untouched() {
+    let mut acc = InfoAccumulator::new(MergeStrategy::LastWriteWins);
+
+    acc.push("info depth 10 nodes 100").unwrap();
+
+    assert!(acc.push("info depth 11 nodes x").is_err());
+    assert_eq!(acc.ai().depth, 10);
+}
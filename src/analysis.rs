@@ -1,11 +1,28 @@
+use crate::prelude::*;
+
 use log::{error, warn};
 
+#[cfg(feature = "std")]
 use envor::envor::env_true;
 
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use thiserror::Error;
 
+use core::convert::TryFrom;
+
+/// BinaryDecodeError captures possible binary analysis info decoding errors
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BinaryDecodeError {
+    #[error("binary analysis info has length {0}, expected {BINARY_LEN}")]
+    Length(usize),
+    #[error("binary analysis info has version {0}, expected {BINARY_VERSION}")]
+    Version(u8),
+    #[error("binary analysis info has an invalid move or pv")]
+    InvalidBuff,
+}
+
 /// InfoParseError captures possible info parsing errors
 #[derive(Error, Debug)]
 pub enum InfoParseError {
@@ -15,6 +32,8 @@ pub enum InfoParseError {
     InvalidKeyError(String),
     #[error("invalid score specifier '{0}'")]
     InvalidScoreSpecifier(String),
+    #[error("info key for state '{0:?}' has no value")]
+    MissingValue(ParsingState),
 }
 
 /// log info parse error and return it as a result
@@ -26,154 +45,266 @@ pub fn info_parse_error(err: InfoParseError) -> Result<(), InfoParseError> {
 
 /// log parse number error and return it as a result
 pub fn parse_number_error<T: AsRef<str>>(ps: ParsingState, value: T) -> Result<(), InfoParseError> {
-    let value = value.as_ref().to_string();
+    Err(number_error(ps, value.as_ref()))
+}
+
+/// log parse number error and return it
+fn number_error(ps: ParsingState, value: &str) -> InfoParseError {
+    let err = InfoParseError::ParseNumberError(ps, value.to_string());
+
+    error!("{:?}", err);
+
+    err
+}
+
+/// whether unknown info keys should be skipped instead of failing the parse,
+/// controlled by the ALLOW_UNKNOWN_INFO_KEY environment variable
+#[cfg(feature = "std")]
+fn allow_unknown_info_key() -> bool {
+    env_true("ALLOW_UNKNOWN_INFO_KEY")
+}
+
+/// without std there is no environment, unknown info keys always fail the parse
+#[cfg(not(feature = "std"))]
+fn allow_unknown_info_key() -> bool {
+    false
+}
+
+/// integer types info numbers are parsed into
+trait InfoNumber: core::str::FromStr<Err = core::num::ParseIntError> {
+    /// value stored for numbers too large for the type
+    const SATURATED_MAX: Self;
+    /// value stored for numbers too small for the type
+    const SATURATED_MIN: Self;
+}
+
+impl InfoNumber for usize {
+    const SATURATED_MAX: Self = usize::MAX;
+    const SATURATED_MIN: Self = usize::MIN;
+}
+
+impl InfoNumber for u64 {
+    const SATURATED_MAX: Self = u64::MAX;
+    const SATURATED_MIN: Self = u64::MIN;
+}
+
+impl InfoNumber for u32 {
+    const SATURATED_MAX: Self = u32::MAX;
+    const SATURATED_MIN: Self = u32::MIN;
+}
+
+impl InfoNumber for i32 {
+    const SATURATED_MAX: Self = i32::MAX;
+    const SATURATED_MIN: Self = i32::MIN;
+}
+
+/// thousands separators printed by engines under non C locales, a dot is not one
+/// as a decimal number such as 1.234 would be read as an integer scaled by 1000
+const THOUSANDS_SEPARATORS: [char; 2] = [',', '_'];
+
+/// remove thousands separators from number, None if the number has no
+/// separators or they do not split it into groups of three digits
+fn strip_thousands_separators(token: &str) -> Option<String> {
+    let separator = token.chars().find(|c| THOUSANDS_SEPARATORS.contains(c))?;
+
+    let (sign, digits) = match token.strip_prefix(['+', '-']) {
+        Some(digits) => (&token[0..1], digits),
+        _ => ("", token),
+    };
+
+    let groups: Vec<&str> = digits.split(separator).collect();
+
+    let valid_group = |group: &&str| group.chars().all(|c| c.is_ascii_digit());
+
+    if !(1..=3).contains(&groups[0].len())
+        || groups[1..].iter().any(|group| group.len() != 3)
+        || !groups.iter().all(valid_group)
+    {
+        return None;
+    }
+
+    Some(format!("{}{}", sign, groups.concat()))
+}
+
+/// parse info number, tolerating explicit plus signs ( +35 ),
+/// thousands separators ( 1,234,567 1_234_567 )
+/// and numbers too large for the field, which are saturated,
+/// if strict only plain numbers within range are accepted
+fn parse_number<T: InfoNumber>(token: &str, strict: bool) -> Result<T, core::num::ParseIntError> {
+    let parse_result = match strip_thousands_separators(token) {
+        Some(stripped) if !strict => stripped.parse::<T>(),
+        _ => token.parse::<T>(),
+    };
+
+    match parse_result {
+        Err(err) if strict => Err(err),
+        Err(err) => match err.kind() {
+            core::num::IntErrorKind::PosOverflow => Ok(T::SATURATED_MAX),
+            core::num::IntErrorKind::NegOverflow => Ok(T::SATURATED_MIN),
+            _ => Err(err),
+        },
+        ok => ok,
+    }
+}
+
+/// StrBuffOverflow reports a value too long for a string buffer
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("value of {len} bytes does not fit string buffer of {capacity} bytes")]
+pub struct StrBuffOverflow {
+    /// length of the value in bytes
+    pub len: usize,
+    /// capacity of the buffer in bytes
+    pub capacity: usize,
+}
+
+/// copyable string buffer holding at most N bytes of utf-8 without allocating
+#[derive(Clone, Copy)]
+pub struct StrBuff<const N: usize> {
+    pub len: usize,
+    pub buff: [u8; N],
+}
+
+/// string buffer implementation
+impl<const N: usize> StrBuff<N> {
+    /// create new empty buffer
+    pub fn new() -> Self {
+        Self {
+            len: 0,
+            buff: [0; N],
+        }
+    }
+
+    /// capacity in bytes
+    pub const fn capacity() -> usize {
+        N
+    }
+
+    /// contents, up to the first invalid utf-8 sequence should len or buff be set directly
+    pub fn as_str(&self) -> &str {
+        let bytes = &self.buff[0..self.len.min(N)];
+
+        match core::str::from_utf8(bytes) {
+            Ok(contents) => contents,
+            Err(err) => core::str::from_utf8(&bytes[0..err.valid_up_to()]).unwrap_or_default(),
+        }
+    }
+
+    /// convert to option ( None if empty, Some(contents) otherwise )
+    pub fn to_opt(self) -> Option<String> {
+        if self.len == 0 {
+            return None;
+        }
+
+        Some(String::from(self))
+    }
+
+    /// set contents ( a value that does not fit is cut at the last char boundary
+    /// within buffer size, so that no char is split )
+    pub fn set<T: AsRef<str>>(&mut self, value: T) -> Self {
+        let value = value.as_ref();
+
+        let mut len = value.len().min(N);
+
+        while !value.is_char_boundary(len) {
+            len -= 1;
+        }
+
+        self.store(&value[0..len])
+    }
+
+    /// reset to empty buffer
+    pub fn reset(&mut self) -> Self {
+        self.len = 0;
+
+        *self
+    }
+
+    /// set contents ( a value that does not fit is cut at the last trim char
+    /// that keeps it within buffer size, or emptied if there is none )
+    pub fn set_trim<T: AsRef<str>>(&mut self, value: T, trim: char) -> Self {
+        let value = value.as_ref();
 
-    info_parse_error(InfoParseError::ParseNumberError(ps, value))
+        let mut len = value.len();
+
+        if len > N {
+            len = value
+                .char_indices()
+                .rev()
+                .find(|(i, c)| (*c == trim) && (*i <= N))
+                .map(|(i, _)| i)
+                .unwrap_or(0);
+        }
+
+        self.store(&value[0..len])
+    }
+
+    /// store value that fits the buffer
+    fn store(&mut self, value: &str) -> Self {
+        self.len = value.len();
+
+        self.buff[0..self.len].copy_from_slice(value.as_bytes());
+
+        *self
+    }
+}
+
+/// default string buffer
+impl<const N: usize> Default for StrBuff<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// string buffer from value that fits, reporting overflow otherwise
+impl<const N: usize> TryFrom<&str> for StrBuff<N> {
+    type Error = StrBuffOverflow;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        if value.len() > N {
+            return Err(StrBuffOverflow {
+                len: value.len(),
+                capacity: N,
+            });
+        }
+
+        Ok(Self::new().store(value))
+    }
+}
+
+/// string buffer from string, a value that does not fit is truncated as by set,
+/// use try_from on the str to detect overflow
+impl<const N: usize> core::convert::From<String> for StrBuff<N> {
+    fn from(value: String) -> Self {
+        Self::new().set(value)
+    }
+}
+
+/// string buffer from optional string, empty if None, truncated as by set
+impl<const N: usize> core::convert::From<Option<String>> for StrBuff<N> {
+    fn from(value: Option<String>) -> Self {
+        Self::new().set(value.unwrap_or_default())
+    }
+}
+
+/// string from string buffer
+impl<const N: usize> core::convert::From<StrBuff<N>> for String {
+    fn from(buff: StrBuff<N>) -> String {
+        buff.as_str().to_string()
+    }
 }
 
-/// generate string buffer with given name and size
-macro_rules! gen_str_buff {
-	($(#[$attr:meta] => $type:ident, $size:expr),*) => { $(
-	    #[$attr]
-	    #[derive(Clone, Copy)]
-		pub struct $type {
-			pub len: usize,
-			pub buff: [u8; $size],
-		}
+/// display string buffer contents
+impl<const N: usize> core::fmt::Display for StrBuff<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
 
-		#[$attr]
-		#[doc = "implementation"]
-		impl $type {
-			#[doc = "create new"]
-			#[$attr]
-			pub fn new() -> Self {
-				Self {
-					len: 0,
-					buff: [0; $size]
-				}
-			}
-
-			#[doc = "convert"]
-			#[$attr]
-			#[doc = "to option ( None if empty, Some(contents) otherwise )"]
-			pub fn to_opt(self) -> Option<String> {
-				if self.len == 0 {
-					return None;
-				}
-
-				Some(String::from(self))
-			}
-
-			#[doc = "set"]
-			#[$attr]
-			#[doc = "( value will be trimmed to buffer size )"]
-			pub fn set<T: AsRef<str>>(&mut self, value: T) -> Self {
-				let bytes = value.as_ref().as_bytes();
-
-				let mut len = bytes.len();
-
-				if len > $size{
-					len = $size;
-				}
-
-				self.len = len;
-
-				self.buff[0..len].copy_from_slice(&bytes[0..len]);
-
-				*self
-			}
-
-			#[doc = "reset"]
-			#[$attr]
-			#[doc = "to empty buffer"]
-			pub fn reset(&mut self) -> Self {
-				self.len = 0;
-
-				*self
-			}
-
-			pub fn set_trim<T: AsRef<str>>(&mut self, value: T, trim: char) -> Self {
-				let value_ref = value.as_ref();
-				let value_string = value_ref.to_string();
-				let bytes = value_ref.as_bytes();
-
-				let mut total_len = value_string.len();
-
-			    value_ref.to_string().chars().rev().take_while(|c| {
-			        total_len -= 1;
-			        ( *c != trim ) || ( total_len > $size )
-			    }).collect::<String>().len();
-
-			    self.len = total_len;
-
-			    self.buff[0..total_len].copy_from_slice(&bytes[0..total_len]);
-
-				*self
-			}
-		}
-
-		#[doc = "implement From<&str> for"]
-		#[$attr]
-		impl std::convert::From<&str> for $type {
-			fn from(value: &str) -> Self {
-				let bytes = value.as_bytes();
-
-				let mut len = bytes.len();
-
-				if len > $size{
-					len = $size;
-				}
-
-				let mut buff = $type::new();
-
-                buff.len = len;
-				buff.buff[0..len].copy_from_slice(&bytes[0..len]);
-
-				buff
-			}
-		}
-
-		#[doc = "implement From<String> for"]
-		#[$attr]
-		impl std::convert::From<String> for $type {
-			fn from(value: String) -> Self {
-				Self::from(value.as_str())
-			}
-		}
-
-		#[doc = "implement From<Option<String>> for"]
-		#[$attr]
-		impl std::convert::From<Option<String>> for $type {
-			fn from(value: Option<String>) -> Self {
-				Self::from(value.unwrap_or(String::new()).as_str())
-			}
-		}
-
-		#[doc = "implement From<"]
-		#[$attr]
-		#[doc = "> for String"]
-		impl std::convert::From<$type> for String {
-			fn from(buff: $type) -> String {
-				std::str::from_utf8(&buff.buff[0..buff.len]).unwrap().to_string()
-			}
-		}
-
-		#[doc = "implement Display for"]
-		#[$attr]
-		impl std::fmt::Display for $type {
-			fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		        write!(f, "{}", String::from(*self))
-		    }
-		}
-
-		#[doc = "implement Debug for"]
-		#[$attr]
-		impl std::fmt::Debug for $type {
-			fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		        write!(f, "[{}[{}]: '{}']", stringify!($type), self.len, String::from(*self))
-		    }
-		}
-	)* }
+/// debug string buffer with capacity and length
+impl<const N: usize> core::fmt::Debug for StrBuff<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "[StrBuff<{}>[{}]: '{}']", N, self.len, self.as_str())
+    }
 }
 
 /// maximum length of uci move
@@ -188,15 +319,316 @@ const MAX_PV_MOVES: usize = 2;
 /// pv buffer size
 const PV_BUFF_SIZE: usize = MAX_PV_MOVES * (UCI_TYPICAL_LENGTH + 1);
 
-gen_str_buff!(
-/// UciBuff
-=> UciBuff, UCI_MAX_LENGTH,
-/// PvBuff
-=> PvBuff, PV_BUFF_SIZE
-);
+/// uci move buffer
+pub type UciBuff = StrBuff<UCI_MAX_LENGTH>;
+
+/// pv buffer
+pub type PvBuff = StrBuff<PV_BUFF_SIZE>;
+
+/// version of the binary analysis info encoding
+pub const BINARY_VERSION: u8 = 1;
+
+/// size of a move in the binary encoding, fixed by the format rather than the move buffer
+const BINARY_UCI_SIZE: usize = 5;
+
+/// size of the pv in the binary encoding, fixed by the format rather than the pv buffer,
+/// so that the record length does not change with MAX_PV_MOVES
+const BINARY_PV_SIZE: usize = 50;
+
+/// length of a string buffer in the binary encoding ( length byte followed by contents )
+const fn binary_buff_len(size: usize) -> usize {
+    1 + size
+}
+
+/// length of binary encoded analysis info
+pub const BINARY_LEN: usize = 2
+    + 6 * 8
+    + 7 * 4
+    + 3 * 4
+    + 3 * binary_buff_len(BINARY_UCI_SIZE)
+    + binary_buff_len(BINARY_PV_SIZE);
+
+/// writes fields of the binary encoding in order
+struct BinaryWriter<'a> {
+    /// encoded record
+    bytes: &'a mut [u8],
+    /// position of the next field
+    pos: usize,
+}
+
+/// binary writer implementation
+impl BinaryWriter<'_> {
+    /// write bytes
+    fn put(&mut self, bytes: &[u8]) {
+        self.bytes[self.pos..self.pos + bytes.len()].copy_from_slice(bytes);
+
+        self.pos += bytes.len();
+    }
+
+    /// write usize as u32, saturated
+    fn put_u32(&mut self, value: usize) {
+        self.put(&(value.min(u32::MAX as usize) as u32).to_le_bytes());
+    }
+
+    /// write u64
+    fn put_u64(&mut self, value: u64) {
+        self.put(&value.to_le_bytes());
+    }
+
+    /// write string buffer contents, padded to size
+    fn put_buff(&mut self, contents: &[u8], size: usize) {
+        self.put(&[contents.len() as u8]);
+        self.put(contents);
+
+        self.pos += size - contents.len();
+    }
+}
+
+/// offset of the first u64 field in the binary encoding ( after version and flags )
+const BINARY_U64_POS: usize = 2;
+
+/// offset of the first u32 field in the binary encoding
+const BINARY_U32_POS: usize = BINARY_U64_POS + 6 * 8;
+
+/// offset of the first string buffer in the binary encoding
+const BINARY_BUFF_POS: usize = BINARY_U32_POS + 7 * 4 + 3 * 4;
+
+/// borrowed view of a binary analysis info record, reading fields from the record
+/// without copying, e.g. from a memory mapped file of consecutive records
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnalysisInfoBin<'a>(&'a [u8; BINARY_LEN]);
+
+/// binary analysis info view implementation
+impl<'a> AnalysisInfoBin<'a> {
+    /// create view of record created by to_binary, checking its length, version and buffers
+    pub fn new(bytes: &'a [u8]) -> Result<Self, BinaryDecodeError> {
+        let record = match <&[u8; BINARY_LEN]>::try_from(bytes) {
+            Ok(record) => Self(record),
+            _ => return Err(BinaryDecodeError::Length(bytes.len())),
+        };
+
+        if bytes[0] != BINARY_VERSION {
+            return Err(BinaryDecodeError::Version(bytes[0]));
+        }
+
+        for index in 0..4 {
+            record.buff(index)?;
+        }
+
+        Ok(record)
+    }
+
+    /// record bytes
+    pub fn bytes(self) -> &'a [u8; BINARY_LEN] {
+        self.0
+    }
+
+    /// flags byte
+    fn flags(self) -> u8 {
+        self.0[1]
+    }
+
+    /// read N bytes at pos
+    fn take<const N: usize>(self, pos: usize) -> [u8; N] {
+        let mut bytes = [0; N];
+
+        bytes.copy_from_slice(&self.0[pos..pos + N]);
+
+        bytes
+    }
+
+    /// u64 field by index
+    fn u64(self, index: usize) -> u64 {
+        u64::from_le_bytes(self.take(BINARY_U64_POS + 8 * index))
+    }
+
+    /// u32 field by index, as usize
+    fn u32(self, index: usize) -> usize {
+        u32::from_le_bytes(self.take(BINARY_U32_POS + 4 * index)) as usize
+    }
+
+    /// string buffer contents by index ( bestmove, ponder, currmove, pv )
+    fn buff(self, index: usize) -> Result<&'a str, BinaryDecodeError> {
+        let (pos, size) = match index {
+            3 => (
+                BINARY_BUFF_POS + 3 * binary_buff_len(BINARY_UCI_SIZE),
+                BINARY_PV_SIZE,
+            ),
+            _ => (
+                BINARY_BUFF_POS + index * binary_buff_len(BINARY_UCI_SIZE),
+                BINARY_UCI_SIZE,
+            ),
+        };
+
+        let len = self.0[pos] as usize;
+
+        if len > size {
+            return Err(BinaryDecodeError::InvalidBuff);
+        }
+
+        core::str::from_utf8(&self.0[pos + 1..pos + 1 + len])
+            .map_err(|_| BinaryDecodeError::InvalidBuff)
+    }
+
+    /// string buffer contents by index, None if empty
+    fn buff_opt(self, index: usize) -> Option<&'a str> {
+        self.buff(index)
+            .ok()
+            .filter(|contents| !contents.is_empty())
+    }
+
+    /// whether the search is done
+    pub fn done(self) -> bool {
+        self.flags() & 1 != 0
+    }
+
+    /// score type
+    pub fn scoretype(self) -> ScoreType {
+        match self.flags() & 6 {
+            2 => ScoreType::Lowerbound,
+            4 => ScoreType::Upperbound,
+            _ => ScoreType::Exact,
+        }
+    }
+
+    /// generation
+    pub fn generation(self) -> u64 {
+        self.u64(0)
+    }
+
+    /// time searched in milliseconds
+    pub fn time(self) -> usize {
+        self.u64(1) as usize
+    }
+
+    /// nodes searched
+    pub fn nodes(self) -> u64 {
+        self.u64(2)
+    }
+
+    /// nodes per second
+    pub fn nps(self) -> u64 {
+        self.u64(3)
+    }
+
+    /// tablebase hits
+    pub fn tbhits(self) -> u64 {
+        self.u64(4)
+    }
+
+    /// shredder tablebase hits
+    pub fn sbhits(self) -> u64 {
+        self.u64(5)
+    }
+
+    /// depth
+    pub fn depth(self) -> usize {
+        self.u32(0)
+    }
+
+    /// selective depth
+    pub fn seldepth(self) -> usize {
+        self.u32(1)
+    }
+
+    /// multipv index
+    pub fn multipv(self) -> usize {
+        self.u32(2)
+    }
+
+    /// current move number
+    pub fn currmovenumber(self) -> usize {
+        self.u32(3)
+    }
+
+    /// hash usage in permill
+    pub fn hashfull(self) -> usize {
+        self.u32(4)
+    }
+
+    /// cpu usage in permill
+    pub fn cpuload(self) -> usize {
+        self.u32(5)
+    }
+
+    /// score
+    pub fn score(self) -> Score {
+        let score = i32::from_le_bytes(self.take(BINARY_U32_POS + 4 * 6));
+
+        if self.flags() & 8 != 0 {
+            Score::Mate(score)
+        } else {
+            Score::Cp(score)
+        }
+    }
+
+    /// win draw loss statistics, None if the engine did not send them
+    pub fn wdl(self) -> Option<Wdl> {
+        if self.flags() & 16 == 0 {
+            return None;
+        }
+
+        let value = |index: usize| u32::from_le_bytes(self.take(BINARY_U32_POS + 4 * index));
+
+        Some(Wdl {
+            win: value(7),
+            draw: value(8),
+            loss: value(9),
+        })
+    }
+
+    /// best move, None if empty
+    pub fn bestmove(self) -> Option<&'a str> {
+        self.buff_opt(0)
+    }
+
+    /// ponder move, None if empty
+    pub fn ponder(self) -> Option<&'a str> {
+        self.buff_opt(1)
+    }
+
+    /// current move, None if empty
+    pub fn currmove(self) -> Option<&'a str> {
+        self.buff_opt(2)
+    }
+
+    /// pv, None if empty
+    pub fn pv(self) -> Option<&'a str> {
+        self.buff_opt(3)
+    }
+
+    /// copy into owned analysis info
+    pub fn to_analysis_info(self) -> AnalysisInfo {
+        let mut ai = AnalysisInfo::new();
+
+        ai.done = self.done();
+        ai.scoretype = self.scoretype();
+        ai.generation = self.generation();
+        ai.time = self.time();
+        ai.nodes = self.nodes();
+        ai.nps = self.nps();
+        ai.tbhits = self.tbhits();
+        ai.sbhits = self.sbhits();
+        ai.depth = self.depth();
+        ai.seldepth = self.seldepth();
+        ai.multipv = self.multipv();
+        ai.currmovenumber = self.currmovenumber();
+        ai.hashfull = self.hashfull();
+        ai.cpuload = self.cpuload();
+        ai.score = self.score();
+        ai.wdl = self.wdl();
+        ai.bestmove = UciBuff::new().set(self.bestmove().unwrap_or_default());
+        ai.ponder = UciBuff::new().set(self.ponder().unwrap_or_default());
+        ai.currmove = UciBuff::new().set(self.currmove().unwrap_or_default());
+        ai.pv = PvBuff::new().set_trim(self.pv().unwrap_or_default(), ' ');
+
+        ai
+    }
+}
 
 /// score
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Score {
     /// centipawn
     Cp(i32),
@@ -204,71 +636,387 @@ pub enum Score {
     Mate(i32),
 }
 
-/// score type
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-pub enum ScoreType {
-    /// exact
-    Exact,
-    /// lowerbound
-    Lowerbound,
-    /// upperbound
-    Upperbound,
+/// side to move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Color {
+    /// white
+    White,
+    /// black
+    Black,
 }
 
-// http://wbec-ridderkerk.nl/html/UCIProtocol.html
-//
-// * info
-// 	the engine wants to send infos to the GUI. This should be done whenever one of the info has changed.
-// 	The engine can send only selected infos and multiple infos can be send with one info command,
-// 	e.g. "info currmove e2e4 currmovenumber 1" or
-// 	     "info depth 12 nodes 123456 nps 100000".
-// 	Also all infos belonging to the pv should be sent together
-// 	e.g. "info depth 2 score cp 214 time 1242 nodes 2124 nps 34928 pv e2e4 e7e5 g1f3"
-// 	I suggest to start sending "currmove", "currmovenumber", "currline" and "refutation" only after one second
-// 	to avoid too much traffic.
-// 	Additional info:
-// 	* depth
-// 		search depth in plies
-// 	* seldepth
-// 		selective search depth in plies,
-// 		if the engine sends seldepth there must also a "depth" be present in the same string.
-// 	* time
-// 		the time searched in ms, this should be sent together with the pv.
-// 	* nodes
-// 		x nodes searched, the engine should send this info regularly
-// 	* pv  ...
-// 		the best line found
-// 	* multipv
-// 		this for the multi pv mode.
-// 		for the best move/pv add "multipv 1" in the string when you send the pv.
-// 		in k-best mode always send all k variants in k strings together.
-// 	* score
-// 		* cp
-// 			the score from the engine's point of view in centipawns.
-// 		* mate
-// 			mate in y moves, not plies.
-// 			If the engine is getting mated use negativ values for y.
-// 		* lowerbound
-// 	      the score is just a lower bound.
-// 		* upperbound
-// 		   the score is just an upper bound.
-// 	* currmove
-// 		currently searching this move
-// 	* currmovenumber
-// 		currently searching move number x, for the first move x should be 1 not 0.
-// 	* hashfull
-// 		the hash is x permill full, the engine should send this info regularly
-// 	* nps
-// 		x nodes per second searched, the engine should send this info regularly
-// 	* tbhits
-// 		x positions where found in the endgame table bases
-// 	* cpuload
-// 		the cpu usage of the engine is x permill.
-// 	* string
-// 		any string str which will be displayed be the engine,
-// 		if there is a string command the rest of the line will be interpreted as .
-// 	* refutation   ...
-// 	   move  is refuted by the line  ... , i can be any number >= 1.
+/// color implementation
+impl Color {
+    /// the other side
+    pub fn opposite(self) -> Self {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+}
+
+/// value of a mate on the board in score_value, beyond any centipawn score
+const MATE_VALUE: i64 = 1 << 40;
+
+/// default slope of the logistic win probability model per centipawn
+/// ( fitted by lichess on games of rated players )
+pub const WIN_PROBABILITY_SCALE: f64 = 0.00368208;
+
+/// score implementation
+impl Score {
+    /// comparable value of the score, centipawns for centipawn scores,
+    /// mates are beyond any centipawn score, with shorter mates further out
+    /// ( mate 1 > mate 5 > any cp > mate -5 > mate -1 )
+    pub fn score_value(self) -> i64 {
+        match self {
+            Score::Cp(cp) => i64::from(cp),
+            Score::Mate(moves) if moves > 0 => MATE_VALUE - i64::from(moves),
+            Score::Mate(moves) => -MATE_VALUE - i64::from(moves),
+        }
+    }
+
+    /// expected points of the side to move ( 0.0 to 1.0, draws counting half ),
+    /// a logistic function of the centipawns with slope scale, mates are certain
+    #[cfg(feature = "std")]
+    pub fn expected_score_with(self, scale: f64) -> f64 {
+        match self {
+            Score::Cp(cp) => 1.0 / (1.0 + (-scale * f64::from(cp)).exp()),
+            Score::Mate(moves) if moves > 0 => 1.0,
+            Score::Mate(_) => 0.0,
+        }
+    }
+
+    /// expected points of the side to move with the default scale
+    #[cfg(feature = "std")]
+    pub fn expected_score(self) -> f64 {
+        self.expected_score_with(WIN_PROBABILITY_SCALE)
+    }
+
+    /// win probability of the side to move as percentage, with slope scale
+    /// ( expected score in percent, the basis of accuracy metrics )
+    #[cfg(feature = "std")]
+    pub fn win_probability_with(self, scale: f64) -> f64 {
+        self.expected_score_with(scale) * 100.0
+    }
+
+    /// win probability of the side to move as percentage with the default scale
+    #[cfg(feature = "std")]
+    pub fn win_probability(self) -> f64 {
+        self.win_probability_with(WIN_PROBABILITY_SCALE)
+    }
+
+    /// score from the point of view of white, given the side to move the engine
+    /// reported it for ( mate 0, the side to move being mated, has no sign and is kept )
+    pub fn to_white_pov(self, side: Color) -> Self {
+        match (side, self) {
+            (Color::White, _) => self,
+            (Color::Black, Score::Cp(cp)) => Score::Cp(cp.saturating_neg()),
+            (Color::Black, Score::Mate(moves)) => Score::Mate(moves.saturating_neg()),
+        }
+    }
+
+    /// score with cp centipawns added, saturating at the centipawn range,
+    /// mate scores are kept
+    pub fn saturating_add(self, cp: i32) -> Self {
+        match self {
+            Score::Cp(score) => Score::Cp(score.saturating_add(cp)),
+            mate => mate,
+        }
+    }
+
+    /// score with cp centipawns subtracted, saturating at the centipawn range,
+    /// mate scores are kept
+    pub fn saturating_sub(self, cp: i32) -> Self {
+        match self {
+            Score::Cp(score) => Score::Cp(score.saturating_sub(cp)),
+            mate => mate,
+        }
+    }
+
+    /// score in uci form ( cp 123, mate -3 )
+    pub fn to_uci(self) -> String {
+        match self {
+            Score::Cp(cp) => format!("cp {}", cp),
+            Score::Mate(moves) => format!("mate {}", moves),
+        }
+    }
+}
+
+/// score of the position before the move, given the score after it, as in negamax,
+/// being mated in n moves after the move is mating in n + 1 moves before it
+/// ( -mate -1 is mate 2, -mate 0 is mate 1 ), mating in n moves after the move
+/// is being mated in n moves before it ( -mate 3 is mate -3 )
+impl core::ops::Neg for Score {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        match self {
+            Score::Cp(cp) => Score::Cp(cp.saturating_neg()),
+            Score::Mate(moves) if moves > 0 => Score::Mate(-moves),
+            Score::Mate(moves) => Score::Mate(moves.saturating_neg().saturating_add(1)),
+        }
+    }
+}
+
+/// scores are ordered by score_value, mating scores beat any centipawn score
+/// and being mated loses to everything
+impl Ord for Score {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.score_value().cmp(&other.score_value())
+    }
+}
+
+/// partial order consistent with the total order of scores
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// score in the conventional human form, pawns with sign for centipawn scores
+/// ( +1.23, -0.45, 0.00 ), # and moves for mates ( #5, #-3 )
+impl core::fmt::Display for Score {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match *self {
+            Score::Cp(cp) => {
+                let sign = match cp {
+                    0 => "",
+                    _ if cp > 0 => "+",
+                    _ => "-",
+                };
+
+                let cp = cp.unsigned_abs();
+
+                write!(f, "{}{}.{:02}", sign, cp / 100, cp % 100)
+            }
+            Score::Mate(moves) => write!(f, "#{}", moves),
+        }
+    }
+}
+
+/// win / draw / loss statistics ( per mille, from the engine's point of view )
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Wdl {
+    /// win
+    pub win: u32,
+    /// draw
+    pub draw: u32,
+    /// loss
+    pub loss: u32,
+}
+
+/// win / draw / loss implementation
+impl Wdl {
+    /// sum of win, draw and loss ( normally 1000 )
+    pub fn total(self) -> u32 {
+        self.win.saturating_add(self.draw).saturating_add(self.loss)
+    }
+
+    /// share of value in total as percentage, 0 if total is 0
+    fn percent(self, value: u32) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => f64::from(value) * 100.0 / f64::from(total),
+        }
+    }
+
+    /// win percentage
+    pub fn win_percent(self) -> f64 {
+        self.percent(self.win)
+    }
+
+    /// draw percentage
+    pub fn draw_percent(self) -> f64 {
+        self.percent(self.draw)
+    }
+
+    /// loss percentage
+    pub fn loss_percent(self) -> f64 {
+        self.percent(self.loss)
+    }
+
+    /// expected points ( 0.0 to 1.0, draws counting half ), 0.5 if total is 0
+    pub fn expected_score(self) -> f64 {
+        match self.total() {
+            0 => 0.5,
+            total => (f64::from(self.win) + f64::from(self.draw) / 2.0) / f64::from(total),
+        }
+    }
+}
+
+/// refutation of a move ( `info refutation <move> <line>` )
+#[derive(Debug, Clone, Copy)]
+pub struct Refutation {
+    /// refuted move
+    refuted: UciBuff,
+    /// refuting line
+    line: PvBuff,
+}
+
+/// refutation implementation
+impl Refutation {
+    /// get refuted move
+    pub fn refuted(self) -> String {
+        String::from(self.refuted)
+    }
+
+    /// get refuting line, None if the engine found no refutation
+    pub fn line(self) -> Option<String> {
+        self.line.to_opt()
+    }
+
+    /// parse from uci form ( refuted move followed by refuting line )
+    fn from_uci(value: &str) -> Option<Self> {
+        let (refuted, line) = split_first_token(value)?;
+
+        Some(Self {
+            refuted: UciBuff::new().set(refuted),
+            line: PvBuff::new().set_trim(join_moves(line), ' '),
+        })
+    }
+}
+
+/// refutation in uci form
+impl core::fmt::Display for Refutation {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.line() {
+            Some(line) => write!(f, "{} {}", self.refuted, line),
+            _ => write!(f, "{}", self.refuted),
+        }
+    }
+}
+
+/// line currently searched by a cpu ( `info currline <cpunr> <line>` )
+#[derive(Debug, Clone, Copy)]
+pub struct Currline {
+    /// number of the cpu searching the line, 1 if the engine omitted it
+    pub cpunr: usize,
+    /// searched line
+    line: PvBuff,
+}
+
+/// currline implementation
+impl Currline {
+    /// get searched line
+    pub fn line(self) -> Option<String> {
+        self.line.to_opt()
+    }
+
+    /// parse from uci form ( cpu number followed by line ), the cpu number
+    /// may be omitted by engines using a single cpu
+    fn from_uci(value: &str) -> Option<Self> {
+        let (first, rest) = split_first_token(value)?;
+
+        let (cpunr, line) = match parse_number::<usize>(first, false) {
+            Ok(cpunr) => (cpunr, rest),
+            _ => (1, value),
+        };
+
+        Some(Self {
+            cpunr,
+            line: PvBuff::new().set_trim(join_moves(line), ' '),
+        })
+    }
+}
+
+/// currline in uci form
+impl core::fmt::Display for Currline {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.line() {
+            Some(line) => write!(f, "{} {}", self.cpunr, line),
+            _ => write!(f, "{}", self.cpunr),
+        }
+    }
+}
+
+/// moves separated by single spaces
+fn join_moves(line: &str) -> String {
+    line.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// split value into its first token and the rest, None if value is empty
+fn split_first_token(value: &str) -> Option<(&str, &str)> {
+    let value = value.trim();
+
+    if value.is_empty() {
+        return None;
+    }
+
+    match value.split_once(char::is_whitespace) {
+        Some((first, rest)) => Some((first, rest.trim())),
+        _ => Some((value, "")),
+    }
+}
+
+/// score type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ScoreType {
+    /// exact
+    Exact,
+    /// lowerbound
+    Lowerbound,
+    /// upperbound
+    Upperbound,
+}
+
+// http://wbec-ridderkerk.nl/html/UCIProtocol.html
+//
+// * info
+// 	the engine wants to send infos to the GUI. This should be done whenever one of the info has changed.
+// 	The engine can send only selected infos and multiple infos can be send with one info command,
+// 	e.g. "info currmove e2e4 currmovenumber 1" or
+// 	     "info depth 12 nodes 123456 nps 100000".
+// 	Also all infos belonging to the pv should be sent together
+// 	e.g. "info depth 2 score cp 214 time 1242 nodes 2124 nps 34928 pv e2e4 e7e5 g1f3"
+// 	I suggest to start sending "currmove", "currmovenumber", "currline" and "refutation" only after one second
+// 	to avoid too much traffic.
+// 	Additional info:
+// 	* depth
+// 		search depth in plies
+// 	* seldepth
+// 		selective search depth in plies,
+// 		if the engine sends seldepth there must also a "depth" be present in the same string.
+// 	* time
+// 		the time searched in ms, this should be sent together with the pv.
+// 	* nodes
+// 		x nodes searched, the engine should send this info regularly
+// 	* pv  ...
+// 		the best line found
+// 	* multipv
+// 		this for the multi pv mode.
+// 		for the best move/pv add "multipv 1" in the string when you send the pv.
+// 		in k-best mode always send all k variants in k strings together.
+// 	* score
+// 		* cp
+// 			the score from the engine's point of view in centipawns.
+// 		* mate
+// 			mate in y moves, not plies.
+// 			If the engine is getting mated use negativ values for y.
+// 		* lowerbound
+// 	      the score is just a lower bound.
+// 		* upperbound
+// 		   the score is just an upper bound.
+// 	* currmove
+// 		currently searching this move
+// 	* currmovenumber
+// 		currently searching move number x, for the first move x should be 1 not 0.
+// 	* hashfull
+// 		the hash is x permill full, the engine should send this info regularly
+// 	* nps
+// 		x nodes per second searched, the engine should send this info regularly
+// 	* tbhits
+// 		x positions where found in the endgame table bases
+// 	* cpuload
+// 		the cpu usage of the engine is x permill.
+// 	* string
+// 		any string str which will be displayed be the engine,
+// 		if there is a string command the rest of the line will be interpreted as .
+// 	* refutation   ...
+// 	   move  is refuted by the line  ... , i can be any number >= 1.
 // 	   Example: after move d1h5 is searched, the engine can send
 // 	   "info refutation d1h5 g6h5"
 // 	   if g6h5 is the best answer after d1h5 or if g6h5 refutes the move d1h5.
@@ -282,11 +1030,18 @@ pub enum ScoreType {
 // 	   If  is greater than 1, always send all k lines in k strings together.
 // 		The engine should only send this if the option "UCI_ShowCurrLine" is set to true.
 
-/// analysis info
+/// analysis info, serialized in the form of AnalysisInfoSerde
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(into = "AnalysisInfoSerde", from = "AnalysisInfoSerde")
+)]
 pub struct AnalysisInfo {
     /// false for ongoing analysis, true when analysis stopped on bestmove received
     pub done: bool,
+    /// search generation, incremented by the engine wrapper whenever a go command is sent
+    pub generation: u64,
     /// best move
     bestmove: UciBuff,
     /// ponder
@@ -305,6 +1060,12 @@ pub struct AnalysisInfo {
     pub multipv: usize,
     /// score ( centipawns or mate )
     pub score: Score,
+    /// win / draw / loss
+    wdl: Option<Wdl>,
+    /// refutation
+    refutation: Option<Refutation>,
+    /// currline
+    currline: Option<Currline>,
     /// current move
     pub currmove: UciBuff,
     /// current move number
@@ -315,6 +1076,8 @@ pub struct AnalysisInfo {
     pub nps: u64,
     /// tbhits
     pub tbhits: u64,
+    /// sbhits ( shredderbase hits )
+    pub sbhits: u64,
     /// cpuload
     pub cpuload: usize,
     /// score type
@@ -322,12 +1085,16 @@ pub struct AnalysisInfo {
 }
 
 /// analysis info serde
+#[cfg(feature = "serde")]
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AnalysisInfoSerde {
     /// disposition
     pub disposition: String,
     /// false for ongoing analysis, true when analysis stopped on bestmove received
     pub done: bool,
+    /// search generation, incremented by the engine wrapper whenever a go command is sent
+    #[serde(default)]
+    pub generation: u64,
     /// best move
     pub bestmove: Option<String>,
     /// ponder
@@ -346,6 +1113,15 @@ pub struct AnalysisInfoSerde {
     pub multipv: usize,
     /// score ( centipawns or mate )
     pub score: Score,
+    /// win / draw / loss
+    #[serde(default)]
+    pub wdl: Option<Wdl>,
+    /// refutation ( uci form, refuted move followed by refuting line )
+    #[serde(default)]
+    pub refutation: Option<String>,
+    /// currline ( uci form, cpu number followed by line )
+    #[serde(default)]
+    pub currline: Option<String>,
     /// current move
     pub currmove: Option<String>,
     /// current move number
@@ -356,12 +1132,54 @@ pub struct AnalysisInfoSerde {
     pub nps: u64,
     /// tbhits
     pub tbhits: u64,
+    /// sbhits ( shredderbase hits )
+    #[serde(default)]
+    pub sbhits: u64,
     /// cpuload
     pub cpuload: usize,
     /// score type
     pub scoretype: ScoreType,
 }
 
+/// analysis info to serde
+#[cfg(feature = "serde")]
+impl From<AnalysisInfo> for AnalysisInfoSerde {
+    fn from(ai: AnalysisInfo) -> Self {
+        ai.to_serde()
+    }
+}
+
+/// analysis info from serde
+#[cfg(feature = "serde")]
+impl From<AnalysisInfoSerde> for AnalysisInfo {
+    fn from(ais: AnalysisInfoSerde) -> Self {
+        AnalysisInfo::from_serde(ais)
+    }
+}
+
+/// maximum plausible search depth
+pub const MAX_PLAUSIBLE_DEPTH: usize = 245;
+
+/// nodes above which a reported nps of zero is implausible
+pub const MIN_NODES_FOR_NPS: u64 = 1_000_000;
+
+/// physically implausible info value, reported by parse_checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SuspectField {
+    /// depth or seldepth beyond MAX_PLAUSIBLE_DEPTH
+    Depth(usize),
+    /// nps reported as zero after searching more than MIN_NODES_FOR_NPS nodes
+    ZeroNps(u64),
+    /// time lower than the time of a previous line
+    TimeBackwards {
+        /// time of the previous line
+        previous: usize,
+        /// time of this line
+        current: usize,
+    },
+}
+
 /// parsing state
 #[derive(Debug)]
 #[allow(dead_code)]
@@ -378,23 +1196,39 @@ pub enum ParsingState {
     Score,
     ScoreCp,
     ScoreMate,
+    WdlWin,
+    WdlDraw,
+    WdlLoss,
+    Refutation,
+    RefutationLine,
+    CurrlineCpunr,
+    CurrlineLine,
     Currmove,
     Currmovenumber,
     Hashfull,
     Nps,
     Tbhits,
+    Sbhits,
     Cpuload,
     PvBestmove,
     PvPonder,
     PvRest,
 }
 
+/// default analysis info
+impl Default for AnalysisInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// analysis info implementation
 impl AnalysisInfo {
     /// create new analysis info
     pub fn new() -> Self {
         Self {
             done: false,
+            generation: 0,
             bestmove: UciBuff::new(),
             ponder: UciBuff::new(),
             pv: PvBuff::new(),
@@ -404,21 +1238,27 @@ impl AnalysisInfo {
             nodes: 0,
             multipv: 0,
             score: Score::Cp(0),
+            wdl: None,
+            refutation: None,
+            currline: None,
             currmove: UciBuff::new(),
             currmovenumber: 0,
             hashfull: 0,
             nps: 0,
             tbhits: 0,
+            sbhits: 0,
             cpuload: 0,
             scoretype: ScoreType::Exact,
         }
     }
 
     /// to serde
+    #[cfg(feature = "serde")]
     pub fn to_serde(self) -> AnalysisInfoSerde {
         AnalysisInfoSerde {
             disposition: "AnalysisInfo".to_string(),
             done: self.done,
+            generation: self.generation,
             bestmove: self.bestmove(),
             ponder: self.ponder(),
             pv: self.pv(),
@@ -428,40 +1268,55 @@ impl AnalysisInfo {
             nodes: self.nodes,
             multipv: self.multipv,
             score: self.score,
+            wdl: self.wdl,
+            refutation: self.refutation.map(|refutation| format!("{}", refutation)),
+            currline: self.currline.map(|currline| format!("{}", currline)),
             currmove: self.currmove(),
             currmovenumber: self.currmovenumber,
             hashfull: self.hashfull,
             nps: self.nps,
             tbhits: self.tbhits,
+            sbhits: self.sbhits,
             cpuload: self.cpuload,
             scoretype: self.scoretype,
         }
     }
 
     /// from serde
+    #[cfg(feature = "serde")]
     pub fn from_serde(ais: AnalysisInfoSerde) -> Self {
         Self {
             done: ais.done,
-            bestmove: UciBuff::from(ais.bestmove),
-            ponder: UciBuff::from(ais.ponder),
-            pv: PvBuff::from(ais.pv),
+            generation: ais.generation,
+            bestmove: UciBuff::new().set(ais.bestmove.unwrap_or_default()),
+            ponder: UciBuff::new().set(ais.ponder.unwrap_or_default()),
+            pv: PvBuff::new().set_trim(ais.pv.unwrap_or_default(), ' '),
             depth: ais.depth,
             seldepth: ais.seldepth,
             time: ais.time,
             nodes: ais.nodes,
             multipv: ais.multipv,
             score: ais.score,
-            currmove: UciBuff::from(ais.currmove),
+            wdl: ais.wdl,
+            refutation: ais
+                .refutation
+                .and_then(|refutation| Refutation::from_uci(&refutation)),
+            currline: ais
+                .currline
+                .and_then(|currline| Currline::from_uci(&currline)),
+            currmove: UciBuff::new().set(ais.currmove.unwrap_or_default()),
             currmovenumber: ais.currmovenumber,
             hashfull: ais.hashfull,
             nps: ais.nps,
             tbhits: ais.tbhits,
+            sbhits: ais.sbhits,
             cpuload: ais.cpuload,
             scoretype: ais.scoretype,
         }
     }
 
     /// from json
+    #[cfg(feature = "serde")]
     pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
         match serde_json::from_str::<AnalysisInfoSerde>(json) {
             Ok(ais) => Ok(AnalysisInfo::from_serde(ais)),
@@ -470,10 +1325,80 @@ impl AnalysisInfo {
     }
 
     /// to json
+    #[cfg(feature = "serde")]
     pub fn to_json(self) -> Result<String, serde_json::Error> {
         serde_json::to_string(&self.to_serde())
     }
 
+    /// encode as fixed length binary record ( BINARY_LEN bytes, starting with BINARY_VERSION ),
+    /// integers are little endian, refutation and currline are not encoded
+    pub fn to_binary(self) -> [u8; BINARY_LEN] {
+        let mut bytes = [0; BINARY_LEN];
+
+        let mut writer = BinaryWriter {
+            bytes: &mut bytes,
+            pos: 0,
+        };
+
+        let scoretype_flags = match self.scoretype {
+            ScoreType::Exact => 0,
+            ScoreType::Lowerbound => 2,
+            ScoreType::Upperbound => 4,
+        };
+
+        let (score_flag, score) = match self.score {
+            Score::Cp(score) => (0, score),
+            Score::Mate(score) => (8, score),
+        };
+
+        let wdl_flag = if self.wdl.is_some() { 16 } else { 0 };
+
+        writer.put(&[
+            BINARY_VERSION,
+            (self.done as u8) | scoretype_flags | score_flag | wdl_flag,
+        ]);
+
+        writer.put_u64(self.generation);
+        writer.put_u64(self.time as u64);
+        writer.put_u64(self.nodes);
+        writer.put_u64(self.nps);
+        writer.put_u64(self.tbhits);
+        writer.put_u64(self.sbhits);
+
+        writer.put_u32(self.depth);
+        writer.put_u32(self.seldepth);
+        writer.put_u32(self.multipv);
+        writer.put_u32(self.currmovenumber);
+        writer.put_u32(self.hashfull);
+        writer.put_u32(self.cpuload);
+        writer.put(&score.to_le_bytes());
+
+        let wdl = self.wdl.unwrap_or_default();
+
+        for value in [wdl.win, wdl.draw, wdl.loss] {
+            writer.put(&value.to_le_bytes());
+        }
+
+        for buff in [self.bestmove, self.ponder, self.currmove] {
+            let buff = StrBuff::<BINARY_UCI_SIZE>::new().set(buff.as_str());
+
+            writer.put_buff(buff.as_str().as_bytes(), BINARY_UCI_SIZE);
+        }
+
+        // a pv buffer larger than the pv field is cut at a move boundary
+        let pv = StrBuff::<BINARY_PV_SIZE>::new().set_trim(self.pv.as_str(), ' ');
+
+        writer.put_buff(pv.as_str().as_bytes(), BINARY_PV_SIZE);
+
+        bytes
+    }
+
+    /// decode binary record created by to_binary into owned analysis info,
+    /// use AnalysisInfoBin to read fields without copying the record
+    pub fn from_binary(bytes: &[u8]) -> Result<Self, BinaryDecodeError> {
+        AnalysisInfoBin::new(bytes).map(AnalysisInfoBin::to_analysis_info)
+    }
+
     // get bestmove
     pub fn bestmove(self) -> Option<String> {
         self.bestmove.to_opt()
@@ -489,236 +1414,1169 @@ impl AnalysisInfo {
         self.pv.to_opt()
     }
 
+    /// get pv as individual moves, empty if there is no pv
+    pub fn pv_moves(self) -> Vec<String> {
+        String::from(self.pv)
+            .split_whitespace()
+            .map(|uci| uci.to_string())
+            .collect()
+    }
+
     // get current move
     pub fn currmove(self) -> Option<String> {
         self.currmove.to_opt()
     }
 
-    /// parse info string
-    pub fn parse<T: std::convert::AsRef<str>>(&mut self, info: T) -> Result<(), InfoParseError> {
-        let info = info.as_ref();
-        let mut ps = ParsingState::Info;
-        let mut pv_buff = String::new();
-        let mut pv_on = false;
-
-        let allow_unknown_key = env_true("ALLOW_UNKNOWN_INFO_KEY");
-
-        for token in info.split(" ") {
-            match ps {
-                ParsingState::Info => {
-                    match token {
-                        "info" => ps = ParsingState::Key,
-                        _ => {
-                            // not an info
-                            return Ok(());
-                        }
-                    }
-                }
-                ParsingState::Key => {
-                    if (token == "string") || (token == "refutation") || (token == "currline") {
-                        // string, refutation and currline are not supported
-                        return Ok(());
-                    }
+    /// get last refutation, None if the engine did not send one
+    /// ( it is only sent with UCI_ShowRefutations on )
+    pub fn refutation(self) -> Option<Refutation> {
+        self.refutation
+    }
 
-                    ps = match token {
-                        "lowerbound" => {
-                            self.scoretype = ScoreType::Lowerbound;
+    /// get time searched as duration
+    pub fn time_duration(self) -> core::time::Duration {
+        core::time::Duration::from_millis(self.time as u64)
+    }
 
-                            ParsingState::Key
-                        }
-                        "upperbound" => {
-                            self.scoretype = ScoreType::Upperbound;
+    /// get last currline, None if the engine did not send one
+    /// ( it is only sent with UCI_ShowCurrLine on )
+    pub fn currline(self) -> Option<Currline> {
+        self.currline
+    }
 
-                            ParsingState::Key
-                        }
-                        "depth" => ParsingState::Depth,
-                        "seldepth" => ParsingState::Seldepth,
-                        "time" => ParsingState::Time,
-                        "nodes" => ParsingState::Nodes,
-                        "multipv" => ParsingState::Multipv,
-                        "score" => ParsingState::Score,
-                        "currmove" => ParsingState::Currmove,
-                        "currmovenumber" => ParsingState::Currmovenumber,
-                        "hashfull" => ParsingState::Hashfull,
-                        "nps" => ParsingState::Nps,
-                        "tbhits" => ParsingState::Tbhits,
-                        "cpuload" => ParsingState::Cpuload,
-                        "pv" => ParsingState::PvBestmove,
-                        _ => {
-                            if allow_unknown_key {
-                                ParsingState::Unknown
-                            } else {
-                                return Err(InfoParseError::InvalidKeyError(token.to_string()));
-                            }
-                        }
-                    };
+    /// get win / draw / loss, None if the engine did not send it ( e.g. UCI_ShowWDL is off )
+    pub fn wdl(self) -> Option<Wdl> {
+        self.wdl
+    }
 
-                    if let ParsingState::Score = ps {
-                        self.scoretype = ScoreType::Exact;
-                    }
-                }
-                ParsingState::Score => match token {
-                    "cp" => ps = ParsingState::ScoreCp,
-                    "mate" => ps = ParsingState::ScoreMate,
-                    "upperbound" => self.scoretype = ScoreType::Upperbound,
-                    "lowerbound" => self.scoretype = ScoreType::Lowerbound,
-                    _ => {
-                        // not a valid score specifier
-                        return info_parse_error(InfoParseError::InvalidScoreSpecifier(
-                            token.to_string(),
-                        ));
-                    }
-                },
-                ParsingState::Unknown => {
-                    // ignore this token and hope for the best ( namely that it had a single token arg )
-                    warn!("unknown info key {}", token);
+    /// fraction of the root moves searched at the current depth, counting the move
+    /// being searched, given the number of legal root moves,
+    /// None until the engine reports currmovenumber
+    pub fn root_progress(self, root_moves: usize) -> Option<f64> {
+        if (self.currmovenumber == 0) || (root_moves == 0) {
+            return None;
+        }
 
-                    ps = ParsingState::Key
-                }
-                _ => {
-                    let mut keep_state = false;
-
-                    match ps {
-                        ParsingState::Depth => match token.parse::<usize>() {
-                            Ok(depth) => self.depth = depth,
-                            _ => return parse_number_error(ps, token),
-                        },
-                        ParsingState::Seldepth => match token.parse::<usize>() {
-                            Ok(seldepth) => self.seldepth = seldepth,
-                            _ => return parse_number_error(ps, token),
-                        },
-                        ParsingState::Time => match token.parse::<usize>() {
-                            Ok(time) => self.time = time,
-                            _ => return parse_number_error(ps, token),
-                        },
-                        ParsingState::Nodes => match token.parse::<u64>() {
-                            Ok(nodes) => self.nodes = nodes,
-                            _ => return parse_number_error(ps, token),
-                        },
-                        ParsingState::Multipv => match token.parse::<usize>() {
-                            Ok(multipv) => self.multipv = multipv,
-                            _ => return parse_number_error(ps, token),
-                        },
-                        ParsingState::ScoreCp => match token {
-                            "upperbound" => {
-                                self.scoretype = ScoreType::Upperbound;
-
-                                keep_state = true
-                            }
-                            "lowerbound" => {
-                                self.scoretype = ScoreType::Lowerbound;
+        Some(self.currmovenumber.min(root_moves) as f64 / root_moves as f64)
+    }
 
-                                keep_state = true
-                            }
-                            _ => match token.parse::<i32>() {
-                                Ok(score_cp) => self.score = Score::Cp(score_cp),
-                                _ => return parse_number_error(ps, token),
-                            },
-                        },
-                        ParsingState::ScoreMate => match token {
-                            "upperbound" => {
-                                self.scoretype = ScoreType::Upperbound;
-
-                                keep_state = true
-                            }
-                            "lowerbound" => {
-                                self.scoretype = ScoreType::Lowerbound;
+    /// whether the score is exact, false while the search fails high ( lowerbound )
+    /// or low ( upperbound )
+    pub fn is_exact(self) -> bool {
+        self.scoretype == ScoreType::Exact
+    }
 
-                                keep_state = true
-                            }
-                            _ => match token.parse::<i32>() {
-                                Ok(score_mate) => self.score = Score::Mate(score_mate),
-                                _ => return parse_number_error(ps, token),
-                            },
-                        },
-                        ParsingState::Currmove => {
-                            self.currmove.set(token);
-
-                            ()
-                        }
-                        ParsingState::Currmovenumber => match token.parse::<usize>() {
-                            Ok(currmovenumber) => self.currmovenumber = currmovenumber,
-                            _ => return parse_number_error(ps, token),
-                        },
-                        ParsingState::Hashfull => match token.parse::<usize>() {
-                            Ok(hashfull) => self.hashfull = hashfull,
-                            _ => return parse_number_error(ps, token),
-                        },
-                        ParsingState::Nps => match token.parse::<u64>() {
-                            Ok(nps) => self.nps = nps,
-                            _ => return parse_number_error(ps, token),
-                        },
-                        ParsingState::Tbhits => match token.parse::<u64>() {
-                            Ok(tbhits) => self.tbhits = tbhits,
-                            _ => return parse_number_error(ps, token),
-                        },
-                        ParsingState::Cpuload => match token.parse::<usize>() {
-                            Ok(cpuload) => self.cpuload = cpuload,
-                            _ => return parse_number_error(ps, token),
-                        },
-                        ParsingState::PvBestmove => {
-                            pv_buff = pv_buff + token;
-
-                            self.bestmove = UciBuff::from(token);
-
-                            self.ponder.reset();
-
-                            pv_on = true;
-
-                            ps = ParsingState::PvPonder
-                        }
-                        ParsingState::PvPonder => {
-                            pv_buff = pv_buff + " " + token;
+    /// analysis info with score, score bound and win / draw / loss from the point of view
+    /// of white, given the side to move the engine searched for
+    pub fn to_white_pov(self, side: Color) -> Self {
+        let mut ai = self;
+
+        if side == Color::Black {
+            ai.score = ai.score.to_white_pov(side);
+
+            ai.scoretype = match ai.scoretype {
+                ScoreType::Lowerbound => ScoreType::Upperbound,
+                ScoreType::Upperbound => ScoreType::Lowerbound,
+                scoretype => scoretype,
+            };
+
+            ai.wdl = ai.wdl.map(|wdl| Wdl {
+                win: wdl.loss,
+                draw: wdl.draw,
+                loss: wdl.win,
+            });
+        }
 
-                            self.ponder = UciBuff::from(token);
+        ai
+    }
 
-                            ps = ParsingState::PvRest
-                        }
-                        ParsingState::PvRest => pv_buff = pv_buff + " " + token,
-                        _ => {
-                            // should not happen
-                        }
-                    }
+    /// parse info string like parse, then check the values sent in the line for physically
+    /// implausible ones, suspect values are stored all the same and returned as diagnostics
+    pub fn parse_checked<T: core::convert::AsRef<str>>(
+        &mut self,
+        info: T,
+    ) -> Result<Vec<SuspectField>, InfoParseError> {
+        let info = info.as_ref();
+        let previous_time = self.time;
 
-                    // anything from key pv onwards should be added to pv
-                    // otherwise switch back to parsing key
-                    if (!pv_on) && (!keep_state) {
-                        ps = ParsingState::Key;
-                    }
-                }
+        let line = self.parse_info(info, false)?;
+
+        let mut suspects = vec![];
+
+        for depth in [line.depth, line.seldepth].iter().flatten() {
+            if *depth > MAX_PLAUSIBLE_DEPTH {
+                suspects.push(SuspectField::Depth(*depth));
             }
         }
 
-        self.pv.set_trim(pv_buff, ' ');
+        // nps is only checked when the line reported it, engines may not send it at all,
+        // the nodes may have been sent in an earlier line
+        if line.nps == Some(0) && self.nodes > MIN_NODES_FOR_NPS {
+            suspects.push(SuspectField::ZeroNps(self.nodes));
+        }
 
-        Ok(())
-    }
-}
+        if let Some(time) = line.time {
+            if time < previous_time {
+                suspects.push(SuspectField::TimeBackwards {
+                    previous: previous_time,
+                    current: time,
+                });
+            }
+        }
 
-#[test]
-fn set_trim() {
-    let mut x = PvBuff::new().set("e2e4");
+        for suspect in &suspects {
+            warn!("suspect info value {:?} in '{}'", suspect, info);
+        }
 
-    assert_eq!(x.len, 4);
+        Ok(suspects)
+    }
 
-    assert_eq!(String::from(x), "e2e4".to_string());
+    /// parse info string
+    ///
+    /// lenient, numbers with thousands separators are accepted, numbers out of range
+    /// are saturated and a key without value at the end of the line is ignored
+    pub fn parse<T: core::convert::AsRef<str>>(&mut self, info: T) -> Result<(), InfoParseError> {
+        self.parse_info(info.as_ref(), false).map(|_| ())
+    }
 
-    x.set_trim("e2e4 e7e5 g1f3 b8c6", ' ');
+    /// parse info string strictly, failing on anything parse tolerates :
+    /// numbers that are not plain or out of range, unknown keys ( even if
+    /// ALLOW_UNKNOWN_INFO_KEY is set ) and keys without value
+    pub fn parse_strict<T: core::convert::AsRef<str>>(
+        &mut self,
+        info: T,
+    ) -> Result<(), InfoParseError> {
+        self.parse_info(info.as_ref(), true).map(|_| ())
+    }
 
-    assert_eq!(x.len, 9);
+    /// parse info string, strictly or leniently, into the analysis info,
+    /// returns the values sent in the line
+    fn parse_info<'a>(
+        &mut self,
+        info: &'a str,
+        strict: bool,
+    ) -> Result<AnalysisInfoRef<'a>, InfoParseError> {
+        let line = AnalysisInfoRef::parse_info(info, strict)?;
+
+        if info.split_whitespace().next() == Some("info") {
+            self.merge(&line);
+        }
 
-    assert_eq!(String::from(x), "e2e4 e7e5".to_string());
-}
+        Ok(line)
+    }
 
-#[test]
-fn parse_error() {
-    let mut ai = AnalysisInfo::new();
+    /// store the values sent in an info line, keeping the values it did not send,
+    /// except for the pv that is cleared by any line without pv
+    fn merge(&mut self, line: &AnalysisInfoRef) {
+        let numbers = [
+            (&mut self.depth, line.depth),
+            (&mut self.seldepth, line.seldepth),
+            (&mut self.time, line.time),
+            (&mut self.multipv, line.multipv),
+            (&mut self.currmovenumber, line.currmovenumber),
+            (&mut self.hashfull, line.hashfull),
+            (&mut self.cpuload, line.cpuload),
+        ];
+
+        for (field, value) in numbers {
+            if let Some(value) = value {
+                *field = value;
+            }
+        }
 
-    let _ = ai.parse(
-        "info depth 3 score mate 5 nodes 3000000000 time 3000 nps 1000000 pv e2e4 e7e5 g1f3",
-    );
+        let counters = [
+            (&mut self.nodes, line.nodes),
+            (&mut self.nps, line.nps),
+            (&mut self.tbhits, line.tbhits),
+            (&mut self.sbhits, line.sbhits),
+        ];
 
-    assert_eq!(ai.depth, 3);
+        for (field, value) in counters {
+            if let Some(value) = value {
+                *field = value;
+            }
+        }
+
+        if let Some(score) = line.score {
+            self.score = score;
+        }
+
+        // a bound may be sent without score
+        if line.score.is_some() || (line.scoretype != ScoreType::Exact) {
+            self.scoretype = line.scoretype;
+        }
+
+        if let Some(wdl) = line.wdl {
+            self.wdl = Some(wdl);
+        }
+
+        if let Some(currmove) = line.currmove {
+            self.currmove.set(currmove);
+        }
+
+        if let Some(refutation) = line.refutation.and_then(Refutation::from_uci) {
+            self.refutation = Some(refutation);
+        }
+
+        if let Some(currline) = line.currline.and_then(Currline::from_uci) {
+            self.currline = Some(currline);
+        }
+
+        // an info string ends the line, so that it carries no pv
+        if line.string.is_some() {
+            return;
+        }
+
+        // refutation and currline lines carry moves of other lines, the pv is kept
+        if (line.refutation.is_some() || line.currline.is_some()) && line.pv.is_none() {
+            return;
+        }
+
+        let mut moves = line.pv_moves();
+
+        if let Some(bestmove) = moves.next() {
+            self.bestmove = UciBuff::new().set(bestmove);
+
+            self.ponder.reset();
+        }
+
+        if let Some(ponder) = moves.next() {
+            self.ponder = UciBuff::new().set(ponder);
+        }
+
+        self.pv.set_trim(join_moves(line.pv.unwrap_or("")), ' ');
+    }
+}
+
+/// analysis info of a single info line borrowing moves from the line instead of copying
+/// them into buffers, for consumers parsing many lines ( e.g. MultiPV at high depth ),
+/// values not sent in the line are None
+#[derive(Debug, Clone, Copy)]
+pub struct AnalysisInfoRef<'a> {
+    /// depth
+    pub depth: Option<usize>,
+    /// seldepth
+    pub seldepth: Option<usize>,
+    /// time
+    pub time: Option<usize>,
+    /// nodes
+    pub nodes: Option<u64>,
+    /// multipv
+    pub multipv: Option<usize>,
+    /// score
+    pub score: Option<Score>,
+    /// score type, exact unless the line sent a bound
+    pub scoretype: ScoreType,
+    /// win / draw / loss
+    pub wdl: Option<Wdl>,
+    /// currmove
+    pub currmove: Option<&'a str>,
+    /// currmovenumber
+    pub currmovenumber: Option<usize>,
+    /// hashfull
+    pub hashfull: Option<usize>,
+    /// nps
+    pub nps: Option<u64>,
+    /// tbhits
+    pub tbhits: Option<u64>,
+    /// sbhits
+    pub sbhits: Option<u64>,
+    /// cpuload
+    pub cpuload: Option<usize>,
+    /// refutation, refuted move followed by the refutation line
+    pub refutation: Option<&'a str>,
+    /// currline, cpu number ( if sent ) followed by the line
+    pub currline: Option<&'a str>,
+    /// pv, moves separated by whitespace
+    pub pv: Option<&'a str>,
+    /// info string
+    pub string: Option<&'a str>,
+}
+
+/// rest of line starting at token, token has to be a slice of line
+fn rest_from<'a>(line: &'a str, token: &'a str) -> &'a str {
+    let start = token.as_ptr() as usize - line.as_ptr() as usize;
+
+    line[start..].trim_end()
+}
+
+/// parse optional info number, None if the line ended before the value,
+/// which fails a strict parse
+fn parse_ref_number<T: InfoNumber>(
+    ps: ParsingState,
+    token: Option<&str>,
+    strict: bool,
+) -> Result<Option<T>, InfoParseError> {
+    match token {
+        Some(token) => match parse_number::<T>(token, strict) {
+            Ok(number) => Ok(Some(number)),
+            _ => Err(number_error(ps, token)),
+        },
+        _ if strict => Err(InfoParseError::MissingValue(ps)),
+        _ => Ok(None),
+    }
+}
+
+/// borrowed analysis info implementation
+impl<'a> AnalysisInfoRef<'a> {
+    /// parse info line, leniently like AnalysisInfo::parse, a line that is not an info
+    /// parses to an analysis info without values
+    pub fn parse(line: &'a str) -> Result<Self, InfoParseError> {
+        Self::parse_info(line, false)
+    }
+
+    /// parse info line strictly like AnalysisInfo::parse_strict
+    pub fn parse_strict(line: &'a str) -> Result<Self, InfoParseError> {
+        Self::parse_info(line, true)
+    }
+
+    /// parse info line, strictly or leniently, this is the info grammar
+    /// AnalysisInfo parses with as well
+    fn parse_info(line: &'a str, strict: bool) -> Result<Self, InfoParseError> {
+        let mut ai = Self {
+            depth: None,
+            seldepth: None,
+            time: None,
+            nodes: None,
+            multipv: None,
+            score: None,
+            scoretype: ScoreType::Exact,
+            wdl: None,
+            currmove: None,
+            currmovenumber: None,
+            hashfull: None,
+            nps: None,
+            tbhits: None,
+            sbhits: None,
+            cpuload: None,
+            refutation: None,
+            currline: None,
+            pv: None,
+            string: None,
+        };
+
+        let mut tokens = line.split_whitespace();
+
+        if tokens.next() != Some("info") {
+            return Ok(ai);
+        }
+
+        let allow_unknown_key = !strict && allow_unknown_info_key();
+
+        while let Some(key) = tokens.next() {
+            match key {
+                "depth" => ai.depth = parse_ref_number(ParsingState::Depth, tokens.next(), strict)?,
+                "seldepth" => {
+                    ai.seldepth = parse_ref_number(ParsingState::Seldepth, tokens.next(), strict)?
+                }
+                "time" => ai.time = parse_ref_number(ParsingState::Time, tokens.next(), strict)?,
+                "nodes" => ai.nodes = parse_ref_number(ParsingState::Nodes, tokens.next(), strict)?,
+                "multipv" => {
+                    ai.multipv = parse_ref_number(ParsingState::Multipv, tokens.next(), strict)?
+                }
+                "currmove" => match tokens.next() {
+                    None if strict => {
+                        return Err(InfoParseError::MissingValue(ParsingState::Currmove))
+                    }
+                    currmove => ai.currmove = currmove,
+                },
+                "currmovenumber" => {
+                    ai.currmovenumber =
+                        parse_ref_number(ParsingState::Currmovenumber, tokens.next(), strict)?
+                }
+                "hashfull" => {
+                    ai.hashfull = parse_ref_number(ParsingState::Hashfull, tokens.next(), strict)?
+                }
+                "nps" => ai.nps = parse_ref_number(ParsingState::Nps, tokens.next(), strict)?,
+                "tbhits" => {
+                    ai.tbhits = parse_ref_number(ParsingState::Tbhits, tokens.next(), strict)?
+                }
+                "sbhits" => {
+                    ai.sbhits = parse_ref_number(ParsingState::Sbhits, tokens.next(), strict)?
+                }
+                "cpuload" => {
+                    ai.cpuload = parse_ref_number(ParsingState::Cpuload, tokens.next(), strict)?
+                }
+                "lowerbound" => ai.scoretype = ScoreType::Lowerbound,
+                "upperbound" => ai.scoretype = ScoreType::Upperbound,
+                "score" => {
+                    ai.scoretype = ScoreType::Exact;
+
+                    // whether the score is a mate score, None until cp or mate is read
+                    let mut mate = None;
+
+                    // bounds may come before or after the value
+                    for token in tokens.by_ref() {
+                        match (token, mate) {
+                            ("lowerbound", _) => ai.scoretype = ScoreType::Lowerbound,
+                            ("upperbound", _) => ai.scoretype = ScoreType::Upperbound,
+                            ("cp", None) => mate = Some(false),
+                            ("mate", None) => mate = Some(true),
+                            (_, None) => {
+                                let err = InfoParseError::InvalidScoreSpecifier(token.to_string());
+
+                                error!("{:?}", err);
+
+                                return Err(err);
+                            }
+                            (_, Some(mate)) => {
+                                ai.score = match (mate, parse_number::<i32>(token, strict)) {
+                                    (false, Ok(cp)) => Some(Score::Cp(cp)),
+                                    (true, Ok(mate)) => Some(Score::Mate(mate)),
+                                    (false, _) => {
+                                        return Err(number_error(ParsingState::ScoreCp, token))
+                                    }
+                                    (true, _) => {
+                                        return Err(number_error(ParsingState::ScoreMate, token))
+                                    }
+                                };
+
+                                break;
+                            }
+                        }
+                    }
+
+                    if strict && ai.score.is_none() {
+                        let ps = match mate {
+                            Some(false) => ParsingState::ScoreCp,
+                            Some(true) => ParsingState::ScoreMate,
+                            _ => ParsingState::Score,
+                        };
+
+                        return Err(InfoParseError::MissingValue(ps));
+                    }
+                }
+                "wdl" => {
+                    let win = parse_ref_number(ParsingState::WdlWin, tokens.next(), strict)?;
+                    let draw = parse_ref_number(ParsingState::WdlDraw, tokens.next(), strict)?;
+                    let loss = parse_ref_number(ParsingState::WdlLoss, tokens.next(), strict)?;
+
+                    if let (Some(win), Some(draw), Some(loss)) = (win, draw, loss) {
+                        ai.wdl = Some(Wdl { win, draw, loss });
+                    }
+                }
+                // the following keys take the rest of the line
+                "refutation" | "currline" | "pv" | "string" => {
+                    let rest = tokens.next().map(|token| rest_from(line, token));
+
+                    match key {
+                        "refutation" => ai.refutation = rest,
+                        "currline" => ai.currline = rest,
+                        "pv" => ai.pv = rest,
+                        _ => ai.string = rest,
+                    }
+
+                    if strict && rest.is_none() {
+                        let ps = match key {
+                            "refutation" => ParsingState::Refutation,
+                            "currline" => ParsingState::CurrlineCpunr,
+                            "pv" => ParsingState::PvBestmove,
+                            _ => return Ok(ai),
+                        };
+
+                        return Err(InfoParseError::MissingValue(ps));
+                    }
+
+                    break;
+                }
+                _ => {
+                    if !allow_unknown_key {
+                        return Err(InfoParseError::InvalidKeyError(key.to_string()));
+                    }
+
+                    // ignore this token and hope for the best ( namely that it had a single token arg )
+                    warn!("unknown info key {}", key);
+
+                    tokens.next();
+                }
+            }
+        }
+
+        Ok(ai)
+    }
+
+    /// moves of the pv
+    pub fn pv_moves(&self) -> impl Iterator<Item = &'a str> {
+        self.pv.unwrap_or("").split_whitespace()
+    }
+
+    /// time searched as duration
+    pub fn time_duration(&self) -> Option<core::time::Duration> {
+        self.time
+            .map(|time| core::time::Duration::from_millis(time as u64))
+    }
+
+    /// best move, the first move of the pv
+    pub fn bestmove(&self) -> Option<&'a str> {
+        self.pv_moves().next()
+    }
+
+    /// ponder move, the second move of the pv
+    pub fn ponder(&self) -> Option<&'a str> {
+        self.pv_moves().nth(1)
+    }
+}
+
+#[test]
+fn set_trim() {
+    let mut x = PvBuff::new().set("e2e4");
+
+    assert_eq!(x.len, 4);
+
+    assert_eq!(String::from(x), "e2e4".to_string());
+
+    x.set_trim("e2e4 e7e5 g1f3 b8c6", ' ');
+
+    assert_eq!(x.len, 9);
+
+    assert_eq!(String::from(x), "e2e4 e7e5".to_string());
+
+    // values that fit are kept whole
+    x.set_trim("e2e4", ' ');
+
+    assert_eq!(String::from(x), "e2e4".to_string());
+
+    x.set_trim("e2e4 e7e5", ' ');
+
+    assert_eq!(String::from(x), "e2e4 e7e5".to_string());
+}
+
+#[test]
+fn str_buff_bounds() {
+    assert_eq!(UciBuff::capacity(), UCI_MAX_LENGTH);
+
+    assert_eq!(UciBuff::try_from("e7e8q").unwrap().as_str(), "e7e8q");
+    assert_eq!(
+        UciBuff::try_from("e7e8q+").unwrap_err(),
+        StrBuffOverflow {
+            len: 6,
+            capacity: UCI_MAX_LENGTH
+        }
+    );
+
+    // set truncates without splitting chars
+    assert_eq!(UciBuff::new().set("abcdef").as_str(), "abcde");
+    assert_eq!(UciBuff::new().set("abcé").as_str(), "abcé");
+    assert_eq!(UciBuff::new().set("abcdé").as_str(), "abcd");
+
+    assert_eq!(UciBuff::from("e7e8q+".to_string()).as_str(), "e7e8q");
+    assert_eq!(UciBuff::from(Some("e2e4".to_string())).as_str(), "e2e4");
+    assert_eq!(UciBuff::from(None).len, 0);
+
+    // contents set directly are cut at the first invalid sequence
+    let mut x = UciBuff::new().set("abcé");
+
+    x.len = 4;
+
+    assert_eq!(x.as_str(), "abc");
+    assert_eq!(format!("{:?}", x), "[StrBuff<5>[4]: 'abc']");
+}
+
+#[test]
+fn parse_error() {
+    let mut ai = AnalysisInfo::new();
+
+    let _ = ai.parse(
+        "info depth 3 score mate 5 nodes 3000000000 time 3000 nps 1000000 pv e2e4 e7e5 g1f3",
+    );
+
+    assert_eq!(ai.depth, 3);
     assert_eq!(format!("{:?}", ai.score), format!("{:?}", Score::Mate(5)));
     assert_eq!(format!("{:?}", ai.ponder()), format!("{:?}", Some("e7e5")));
 }
+
+#[test]
+fn parse_monitoring_fields() {
+    let mut ai = AnalysisInfo::new();
+
+    let result = ai.parse("info depth 20 hashfull 643 cpuload 998 tbhits 12 sbhits 7 nps 1500000");
+
+    assert!(result.is_ok());
+    assert_eq!(ai.hashfull, 643);
+    assert_eq!(ai.cpuload, 998);
+    assert_eq!(ai.tbhits, 12);
+    assert_eq!(ai.sbhits, 7);
+    assert!(ai.pv_moves().is_empty());
+
+    ai.parse("info depth 21 time 49382").unwrap();
+
+    assert_eq!(ai.time_duration(), core::time::Duration::from_millis(49382));
+
+    let ai = AnalysisInfoRef::parse("info depth 21 time 1500").unwrap();
+
+    assert_eq!(
+        ai.time_duration(),
+        Some(core::time::Duration::from_millis(1500))
+    );
+}
+
+#[test]
+fn parse_score_bounds() {
+    let mut ai = AnalysisInfo::new();
+
+    ai.parse("info depth 12 score cp 35 lowerbound nodes 1000 pv e2e4")
+        .unwrap();
+
+    assert_eq!(ai.scoretype, ScoreType::Lowerbound);
+    assert_eq!(ai.nodes, 1000);
+    assert!(!ai.is_exact());
+
+    ai.parse("info depth 12 score mate -3 upperbound").unwrap();
+
+    assert_eq!(ai.scoretype, ScoreType::Upperbound);
+
+    ai.parse("info depth 12 score cp 20 pv e2e4").unwrap();
+
+    assert!(ai.is_exact());
+}
+
+#[test]
+fn parse_wdl() {
+    let mut ai = AnalysisInfo::new();
+
+    assert_eq!(ai.wdl(), None);
+
+    ai.parse("info depth 20 score cp 35 wdl 520 430 50 nodes 1000 pv e2e4")
+        .unwrap();
+
+    let wdl = ai.wdl().unwrap();
+
+    assert_eq!(
+        wdl,
+        Wdl {
+            win: 520,
+            draw: 430,
+            loss: 50
+        }
+    );
+    assert_eq!(ai.nodes, 1000);
+    assert!((wdl.win_percent() - 52.0).abs() < 1e-9);
+    assert!((wdl.loss_percent() - 5.0).abs() < 1e-9);
+
+    assert!(ai.parse("info wdl 520 x 50").is_err());
+}
+
+#[test]
+fn parse_currmove_progress() {
+    let mut ai = AnalysisInfo::new();
+
+    assert_eq!(ai.root_progress(20), None);
+
+    ai.parse("info depth 18 currmove g1f3 currmovenumber 5")
+        .unwrap();
+
+    assert_eq!(ai.currmove(), Some("g1f3".to_string()));
+    assert_eq!(ai.currmovenumber, 5);
+    assert_eq!(ai.root_progress(20), Some(0.25));
+    assert_eq!(ai.root_progress(0), None);
+}
+
+#[test]
+fn parse_refutation_and_currline() {
+    let mut ai = AnalysisInfo::new();
+
+    ai.parse("info refutation d1h5 g6h5").unwrap();
+
+    let refutation = ai.refutation().unwrap();
+
+    assert_eq!(refutation.refuted(), "d1h5".to_string());
+    assert_eq!(refutation.line(), Some("g6h5".to_string()));
+
+    ai.parse("info refutation d1h5").unwrap();
+
+    assert_eq!(ai.refutation().unwrap().line(), None);
+
+    ai.parse("info currline 2 e2e4 e7e5").unwrap();
+
+    let currline = ai.currline().unwrap();
+
+    assert_eq!(currline.cpunr, 2);
+    assert_eq!(currline.line(), Some("e2e4 e7e5".to_string()));
+
+    // cpu number omitted
+    ai.parse("info currline d2d4 d7d5").unwrap();
+
+    assert_eq!(ai.currline().unwrap().cpunr, 1);
+    assert_eq!(ai.currline().unwrap().line(), Some("d2d4 d7d5".to_string()));
+
+    #[cfg(feature = "serde")]
+    {
+        let ai = AnalysisInfo::from_json(&ai.to_json().unwrap()).unwrap();
+
+        assert_eq!(format!("{}", ai.refutation().unwrap()), "d1h5");
+        assert_eq!(format!("{}", ai.currline().unwrap()), "1 d2d4 d7d5");
+    }
+}
+
+#[test]
+fn refutation_and_currline_keep_pv() {
+    let mut ai = AnalysisInfo::new();
+
+    ai.parse("info depth 10 score cp 30 pv e2e4 e7e5 g1f3")
+        .unwrap();
+
+    for line in ["info refutation d1h5 g6h5", "info currline 1 d2d4 d7d5"] {
+        ai.parse(line).unwrap();
+
+        assert_eq!(ai.pv(), Some("e2e4 e7e5".to_string()), "{}", line);
+        assert_eq!(ai.bestmove(), Some("e2e4".to_string()));
+        assert_eq!(ai.ponder(), Some("e7e5".to_string()));
+    }
+}
+
+#[test]
+fn binary_round_trip() {
+    let mut ai = AnalysisInfo::new();
+
+    ai.parse(
+        "info depth 24 seldepth 31 multipv 2 score mate -3 upperbound wdl 0 10 990 \
+         nodes 123456789 nps 2500000 time 49382 hashfull 512 pv h7h8q g8h8",
+    )
+    .unwrap();
+
+    ai.generation = 7;
+    ai.done = true;
+
+    let bytes = ai.to_binary();
+
+    assert_eq!(bytes.len(), BINARY_LEN);
+
+    // the record length is part of the format, whatever the buffer sizes
+    assert_eq!(BINARY_LEN, 159);
+    assert_eq!(bytes[0], BINARY_VERSION);
+
+    let decoded = AnalysisInfo::from_binary(&bytes).unwrap();
+
+    #[cfg(feature = "serde")]
+    assert_eq!(decoded.to_json().unwrap(), ai.to_json().unwrap());
+    assert_eq!(decoded.pv(), Some("h7h8q g8h8".to_string()));
+    assert_eq!(decoded.ponder(), Some("g8h8".to_string()));
+
+    // records read in place from consecutive records
+    let mut records = bytes.to_vec();
+
+    records.extend_from_slice(&AnalysisInfo::new().to_binary());
+
+    let views: Vec<AnalysisInfoBin> = records
+        .chunks_exact(BINARY_LEN)
+        .map(|record| AnalysisInfoBin::new(record).unwrap())
+        .collect();
+
+    assert_eq!(views[0].bytes(), &bytes);
+    assert_eq!(views[0].depth(), 24);
+    assert_eq!(views[0].nodes(), 123456789);
+    assert_eq!(views[0].score(), Score::Mate(-3));
+    assert_eq!(views[0].scoretype(), ScoreType::Upperbound);
+    assert_eq!(views[0].wdl().unwrap().loss, 990);
+    assert_eq!(views[0].pv(), Some("h7h8q g8h8"));
+    assert_eq!(views[0].bestmove(), Some("h7h8q"));
+    assert!(views[0].done());
+    assert_eq!(views[1].pv(), None);
+    assert_eq!(views[1].wdl(), None);
+}
+
+#[test]
+fn binary_decode_errors() {
+    let mut bytes = AnalysisInfo::new().to_binary();
+
+    assert_eq!(
+        AnalysisInfo::from_binary(&bytes[1..]).unwrap_err(),
+        BinaryDecodeError::Length(BINARY_LEN - 1)
+    );
+
+    bytes[0] = 0;
+
+    assert_eq!(
+        AnalysisInfo::from_binary(&bytes).unwrap_err(),
+        BinaryDecodeError::Version(0)
+    );
+
+    // bestmove length beyond the buffer size
+    let mut bytes = AnalysisInfo::new().to_binary();
+
+    let bestmove_pos =
+        BINARY_LEN - 3 * binary_buff_len(BINARY_UCI_SIZE) - binary_buff_len(BINARY_PV_SIZE);
+
+    bytes[bestmove_pos] = BINARY_UCI_SIZE as u8 + 1;
+
+    assert_eq!(
+        AnalysisInfo::from_binary(&bytes).unwrap_err(),
+        BinaryDecodeError::InvalidBuff
+    );
+}
+
+#[test]
+fn score_values() {
+    let mut scores = vec![
+        Score::Cp(-30),
+        Score::Mate(5),
+        Score::Mate(-1),
+        Score::Cp(i32::MAX),
+        Score::Mate(1),
+        Score::Mate(-5),
+        Score::Cp(120),
+    ];
+
+    scores.sort_by_key(|score| score.score_value());
+
+    assert_eq!(
+        format!("{:?}", scores),
+        "[Mate(-1), Mate(-5), Cp(-30), Cp(120), Cp(2147483647), Mate(5), Mate(1)]"
+    );
+}
+
+#[test]
+fn parse_strict() {
+    let mut ai = AnalysisInfo::new();
+
+    ai.parse_strict("info depth 12 nodes 1000 score cp -35 lowerbound pv e2e4 e7e5")
+        .unwrap();
+
+    assert_eq!(ai.depth, 12);
+    assert_eq!(ai.score.score_value(), -35);
+
+    for info in [
+        "info depth 12 nodes 1,234,567",
+        "info depth 99999999999999999999999",
+        "info depth 12 score cp",
+        "info depth 12 score cp lowerbound",
+        "info depth 12 foo 5",
+        "info depth 12 pv",
+        "info depth",
+    ] {
+        assert!(AnalysisInfo::new().parse_strict(info).is_err(), "{}", info);
+    }
+
+    // the lenient parse accepts what it can
+    ai.parse("info depth 12 nodes 1,234,567 score cp").unwrap();
+
+    assert_eq!(ai.nodes, 1234567);
+
+    assert!(matches!(
+        AnalysisInfo::new().parse_strict("info depth 12 score mate"),
+        Err(InfoParseError::MissingValue(ParsingState::ScoreMate))
+    ));
+}
+
+#[test]
+fn score_ordering() {
+    assert!(Score::Mate(12) > Score::Cp(i32::MAX));
+    assert!(Score::Mate(1) > Score::Mate(2));
+    assert!(Score::Cp(30) > Score::Cp(-30));
+    assert!(Score::Mate(-12) < Score::Cp(i32::MIN));
+    assert!(Score::Mate(-2) > Score::Mate(-1));
+    assert!(Score::Mate(0) < Score::Mate(-1));
+    assert_eq!(Score::Cp(5), Score::Cp(5));
+    assert_ne!(Score::Cp(0), Score::Mate(0));
+
+    let scores = [
+        Score::Cp(250),
+        Score::Mate(-3),
+        Score::Mate(7),
+        Score::Cp(-40),
+    ];
+
+    assert_eq!(scores.iter().max(), Some(&Score::Mate(7)));
+    assert_eq!(scores.iter().min(), Some(&Score::Mate(-3)));
+}
+
+#[test]
+fn white_pov() {
+    assert_eq!(Score::Cp(35).to_white_pov(Color::White), Score::Cp(35));
+    assert_eq!(Score::Cp(35).to_white_pov(Color::Black), Score::Cp(-35));
+    assert_eq!(Score::Mate(-2).to_white_pov(Color::Black), Score::Mate(2));
+    assert_eq!(
+        Score::Cp(i32::MIN).to_white_pov(Color::Black),
+        Score::Cp(i32::MAX)
+    );
+
+    let mut ai = AnalysisInfo::new();
+
+    ai.parse("info depth 10 score cp 50 lowerbound wdl 300 600 100 pv e7e5")
+        .unwrap();
+
+    let ai = ai.to_white_pov(Color::Black);
+
+    assert_eq!(ai.score, Score::Cp(-50));
+    assert_eq!(ai.scoretype, ScoreType::Upperbound);
+    assert_eq!(ai.wdl().unwrap().win, 100);
+    assert_eq!(ai.wdl().unwrap().loss, 300);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn win_probability() {
+    assert_eq!(Score::Cp(0).expected_score(), 0.5);
+    assert_eq!(Score::Mate(3).win_probability(), 100.0);
+    assert_eq!(Score::Mate(-3).win_probability(), 0.0);
+    assert_eq!(Score::Mate(0).expected_score(), 0.0);
+
+    let win = Score::Cp(100).win_probability();
+
+    assert!((win - 59.1).abs() < 0.1);
+    assert!((Score::Cp(-100).win_probability() - (100.0 - win)).abs() < 1e-9);
+    assert!(Score::Cp(100).win_probability_with(0.01) > win);
+    assert!(Score::Cp(300).expected_score() > Score::Cp(200).expected_score());
+
+    let wdl = Wdl {
+        win: 300,
+        draw: 600,
+        loss: 100,
+    };
+
+    assert_eq!(wdl.expected_score(), 0.6);
+    assert_eq!(Wdl::default().expected_score(), 0.5);
+}
+
+#[test]
+fn score_arithmetic() {
+    assert_eq!(-Score::Cp(35), Score::Cp(-35));
+    assert_eq!(-Score::Cp(i32::MIN), Score::Cp(i32::MAX));
+    assert_eq!(-Score::Mate(3), Score::Mate(-3));
+    assert_eq!(-Score::Mate(-1), Score::Mate(2));
+    assert_eq!(-Score::Mate(0), Score::Mate(1));
+
+    // negamax over the scores after each move
+    let after = [Score::Cp(-20), Score::Mate(-2), Score::Cp(150)];
+
+    assert_eq!(
+        after.iter().map(|score| -*score).max(),
+        Some(Score::Mate(3))
+    );
+
+    assert_eq!(Score::Cp(100).saturating_add(25), Score::Cp(125));
+    assert_eq!(Score::Cp(i32::MAX).saturating_add(1), Score::Cp(i32::MAX));
+    assert_eq!(Score::Cp(i32::MIN).saturating_sub(1), Score::Cp(i32::MIN));
+    assert_eq!(Score::Mate(4).saturating_sub(300), Score::Mate(4));
+}
+
+#[test]
+fn score_display() {
+    let formatted: Vec<String> = [
+        Score::Cp(123),
+        Score::Cp(-45),
+        Score::Cp(0),
+        Score::Cp(-7),
+        Score::Cp(1000),
+        Score::Mate(5),
+        Score::Mate(-3),
+    ]
+    .iter()
+    .map(|score| format!("{} {}", score, score.to_uci()))
+    .collect();
+
+    assert_eq!(
+        formatted,
+        vec![
+            "+1.23 cp 123",
+            "-0.45 cp -45",
+            "0.00 cp 0",
+            "-0.07 cp -7",
+            "+10.00 cp 1000",
+            "#5 mate 5",
+            "#-3 mate -3"
+        ]
+    );
+}
+
+#[test]
+fn parse_messy_whitespace() {
+    let mut ai = AnalysisInfo::new();
+
+    let result = ai.parse("info  depth 7\tseldepth 9   score cp  -23 nodes\t\t12345 pv e2e4  e7e5");
+
+    assert!(result.is_ok());
+    assert_eq!(ai.depth, 7);
+    assert_eq!(ai.seldepth, 9);
+    assert_eq!(format!("{:?}", ai.score), format!("{:?}", Score::Cp(-23)));
+    assert_eq!(ai.nodes, 12345);
+    assert_eq!(ai.bestmove(), Some("e2e4".to_string()));
+    assert_eq!(ai.ponder(), Some("e7e5".to_string()));
+    assert_eq!(ai.pv_moves(), vec!["e2e4", "e7e5"]);
+}
+
+#[test]
+fn parse_leading_and_trailing_whitespace() {
+    let mut ai = AnalysisInfo::new();
+
+    let result = ai.parse("  info depth 12 nps 100000 \r");
+
+    assert!(result.is_ok());
+    assert_eq!(ai.depth, 12);
+    assert_eq!(ai.nps, 100000);
+}
+
+#[test]
+fn parse_thousands_separators() {
+    let mut ai = AnalysisInfo::new();
+
+    let result = ai.parse("info depth 20 nodes 1,234,567 nps 2,000,000 time 1,500");
+
+    assert!(result.is_ok());
+    assert_eq!(ai.nodes, 1234567);
+    assert_eq!(ai.nps, 2000000);
+    assert_eq!(ai.time, 1500);
+}
+
+#[test]
+fn parse_tolerant_numbers() {
+    let mut ai = AnalysisInfo::new();
+
+    let result = ai.parse(
+        "info depth +18 score cp +35 nodes 1,234,567 nps 1_000_000 tbhits 99999999999999999999999",
+    );
+
+    assert!(result.is_ok());
+    assert_eq!(ai.depth, 18);
+    assert_eq!(format!("{:?}", ai.score), format!("{:?}", Score::Cp(35)));
+    assert_eq!(ai.nodes, 1234567);
+    assert_eq!(ai.nps, 1000000);
+    assert_eq!(ai.tbhits, u64::MAX);
+
+    let result = ai.parse("info score cp -1,250");
+
+    assert!(result.is_ok());
+    assert_eq!(format!("{:?}", ai.score), format!("{:?}", Score::Cp(-1250)));
+
+    // separators must split the number into groups of three digits
+    assert!(ai.parse("info nodes 12,34").is_err());
+    assert!(ai.parse("info nodes 1.5").is_err());
+
+    // decimal and apostrophe separated numbers are not integers
+    assert!(ai.parse("info nodes 1.234").is_err());
+    assert!(ai.parse("info nodes 1.234.567").is_err());
+    assert!(ai.parse("info nodes 1'000'000").is_err());
+}
+
+#[test]
+fn parse_checked_suspects() {
+    let mut ai = AnalysisInfo::new();
+
+    let suspects = ai.parse_checked("info depth 20 time 1500 nodes 2000000 nps 1300000");
+
+    assert_eq!(suspects.unwrap(), vec![]);
+
+    let suspects = ai.parse_checked("info depth 250 time 1200 nodes 3000000 nps 0");
+
+    assert_eq!(
+        suspects.unwrap(),
+        vec![
+            SuspectField::Depth(250),
+            SuspectField::ZeroNps(3000000),
+            SuspectField::TimeBackwards {
+                previous: 1500,
+                current: 1200
+            }
+        ]
+    );
+
+    // suspect values are stored all the same
+    assert_eq!(ai.depth, 250);
+
+    // but only flagged in the line that sent them
+    let suspects = ai.parse_checked("info currmove e2e4 currmovenumber 1");
+
+    assert_eq!(suspects.unwrap(), vec![]);
+    assert_eq!(ai.parse_checked("bestmove e2e4").unwrap(), vec![]);
+}
+
+#[test]
+fn parse_borrowed() {
+    let line = String::from(
+        "info depth 20 seldepth 28 multipv 2 score cp 35 lowerbound wdl 80 900 20 \
+         nodes 1,234,567 nps 900000 pv e2e4 e7e5 g1f3  ",
+    );
+
+    let ai = AnalysisInfoRef::parse(&line).unwrap();
+
+    assert_eq!(ai.depth, Some(20));
+    assert_eq!(ai.seldepth, Some(28));
+    assert_eq!(ai.multipv, Some(2));
+    assert_eq!(ai.score.map(|score| score.score_value()), Some(35));
+    assert_eq!(ai.scoretype, ScoreType::Lowerbound);
+    assert_eq!(ai.wdl.unwrap().draw, 900);
+    assert_eq!(ai.nodes, Some(1234567));
+    assert_eq!(ai.time, None);
+    assert_eq!(ai.pv, Some("e2e4 e7e5 g1f3"));
+    assert_eq!(ai.bestmove(), Some("e2e4"));
+    assert_eq!(ai.ponder(), Some("e7e5"));
+
+    let ai = AnalysisInfoRef::parse("info score mate -3 upperbound currmove e1g1").unwrap();
+
+    assert!(matches!(ai.score, Some(Score::Mate(-3))));
+    assert_eq!(ai.scoretype, ScoreType::Upperbound);
+    assert_eq!(ai.currmove, Some("e1g1"));
+    assert_eq!(ai.bestmove(), None);
+
+    let ai = AnalysisInfoRef::parse("info string NNUE enabled").unwrap();
+
+    assert_eq!(ai.string, Some("NNUE enabled"));
+
+    assert!(AnalysisInfoRef::parse("bestmove e2e4")
+        .unwrap()
+        .depth
+        .is_none());
+    assert!(AnalysisInfoRef::parse("info depth x").is_err());
+    assert!(AnalysisInfoRef::parse("info score x 5").is_err());
+}
+
+#[test]
+fn parse_shared_grammar() {
+    // the owned parse stores what the borrowed parse reads
+    let line = "info depth 9 currline 2 e2e4\t e7e5 ";
+
+    let ai_ref = AnalysisInfoRef::parse(line).unwrap();
+
+    let mut ai = AnalysisInfo::new();
+
+    ai.parse("info refutation d1h5  g6h5").unwrap();
+    ai.parse(line).unwrap();
+
+    assert_eq!(ai_ref.depth, Some(ai.depth));
+    assert_eq!(ai.currline.unwrap().cpunr, 2);
+    assert_eq!(ai.currline.unwrap().line(), Some("e2e4 e7e5".to_string()));
+    assert_eq!(ai.refutation.unwrap().line(), Some("g6h5".to_string()));
+
+    ai.parse("info currline e2e4 e7e5").unwrap();
+
+    assert_eq!(ai.currline.unwrap().cpunr, 1);
+
+    // strict rules apply to both
+    for line in [
+        "info depth",
+        "info score cp",
+        "info wdl 100 900",
+        "info pv",
+        "info nodes 1,000",
+    ] {
+        assert!(AnalysisInfoRef::parse_strict(line).is_err(), "{}", line);
+        assert!(AnalysisInfo::new().parse_strict(line).is_err(), "{}", line);
+        assert!(AnalysisInfoRef::parse(line).is_ok(), "{}", line);
+    }
+
+    assert!(AnalysisInfoRef::parse_strict("info string done").is_ok());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_analysis_info() {
+    let mut ai = AnalysisInfo::new();
+
+    ai.parse("info depth 15 score mate 4 wdl 990 10 0 nodes 100000 pv e1e8 a8e8")
+        .unwrap();
+
+    let json = serde_json::to_string(&ai).unwrap();
+
+    assert_eq!(json, ai.to_json().unwrap());
+
+    let decoded: AnalysisInfo = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(decoded.depth, 15);
+    assert_eq!(decoded.wdl(), ai.wdl());
+    assert_eq!(decoded.pv(), Some("e1e8 a8e8".to_string()));
+
+    assert_eq!(
+        serde_json::to_string(&Score::Mate(-2)).unwrap(),
+        r#"{"Mate":-2}"#
+    );
+}
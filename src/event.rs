@@ -0,0 +1,345 @@
+use crate::analysis::*;
+use crate::options::*;
+use crate::prelude::*;
+use crate::trend::*;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// bestmove line ( bestmove e2e4 ponder e7e5, bestmove (none) )
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BestMove {
+    /// best move, None if the engine had no move to play
+    /// ( sent as (none) or as the null move 0000, e.g. when mated or stalemated )
+    pub bestmove: Option<String>,
+    /// ponder move if any
+    pub ponder: Option<String>,
+}
+
+/// bestmove implementation
+impl BestMove {
+    /// parse bestmove line, with or without the leading bestmove token,
+    /// None if the move is missing
+    pub fn parse<T: AsRef<str>>(line: T) -> Option<Self> {
+        let mut tokens = line.as_ref().split_whitespace().peekable();
+
+        if tokens.peek() == Some(&"bestmove") {
+            tokens.next();
+        }
+
+        let bestmove = match tokens.next()? {
+            "(none)" | "0000" => None,
+            bestmove => Some(bestmove.to_string()),
+        };
+
+        let ponder = match (tokens.next(), tokens.next()) {
+            (Some("ponder"), Some(ponder)) => Some(ponder.to_string()),
+            _ => None,
+        };
+
+        Some(Self { bestmove, ponder })
+    }
+
+    /// whether the engine had no move to play
+    pub fn is_none(&self) -> bool {
+        self.bestmove.is_none()
+    }
+}
+
+/// line sent by the engine to the gui
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+// info events are by far the most frequent, boxing them would allocate for every line
+#[allow(clippy::large_enum_variant)]
+pub enum UciEvent {
+    /// info line
+    Info(AnalysisInfo),
+    /// info string line, with the text following info string
+    InfoString(String),
+    /// bestmove line
+    BestMove(BestMove),
+    /// id line ( id name Stockfish 16 )
+    Id {
+        /// id key ( name or author )
+        key: String,
+        /// id value
+        value: String,
+    },
+    /// option declaration
+    Option(UciOptionDef),
+    /// uciok
+    UciOk,
+    /// readyok
+    ReadyOk,
+    /// registration status ( checking, ok or error )
+    Registration(String),
+    /// copy protection status ( checking, ok or error )
+    CopyProtection(String),
+    /// line that is not part of the engine to gui protocol, sent before any protocol line
+    /// ( e.g. a startup banner ), only told apart from unknown lines by the parser
+    Banner(String),
+    /// line that is not part of the engine to gui protocol
+    Unknown(String),
+    /// trend of the score of the current search changed, derived from its info lines
+    /// rather than sent by the engine
+    Trend(Trend),
+}
+
+/// rest of line after skipping count tokens, with surrounding whitespace removed
+fn rest_after(line: &str, count: usize) -> &str {
+    let mut rest = line.trim_start();
+
+    for _ in 0..count {
+        rest = match rest.find(char::is_whitespace) {
+            Some(index) => rest[index..].trim_start(),
+            _ => "",
+        };
+    }
+
+    rest.trim_end()
+}
+
+/// parse engine output line into an event
+///
+/// fails only if an info line fails to parse
+pub fn parse_line<T: AsRef<str>>(line: T) -> Result<UciEvent, InfoParseError> {
+    let line = line.as_ref();
+
+    let mut tokens = line.split_whitespace();
+
+    let event = match tokens.next() {
+        Some("info") if tokens.next() == Some("string") => {
+            UciEvent::InfoString(rest_after(line, 2).to_string())
+        }
+        Some("info") => {
+            let mut ai = AnalysisInfo::new();
+
+            ai.parse(line)?;
+
+            UciEvent::Info(ai)
+        }
+        Some("bestmove") => match BestMove::parse(line) {
+            Some(bestmove) => UciEvent::BestMove(bestmove),
+            _ => UciEvent::Unknown(line.to_string()),
+        },
+        Some("id") => match tokens.next() {
+            Some(key) => UciEvent::Id {
+                key: key.to_string(),
+                value: rest_after(line, 2).to_string(),
+            },
+            _ => UciEvent::Unknown(line.to_string()),
+        },
+        Some("option") => match UciOptionDef::parse(line) {
+            Some(option) => UciEvent::Option(option),
+            _ => UciEvent::Unknown(line.to_string()),
+        },
+        Some("uciok") => UciEvent::UciOk,
+        Some("readyok") => UciEvent::ReadyOk,
+        Some("registration") => UciEvent::Registration(rest_after(line, 1).to_string()),
+        Some("copyprotection") => UciEvent::CopyProtection(rest_after(line, 1).to_string()),
+        _ => UciEvent::Unknown(line.to_string()),
+    };
+
+    Ok(event)
+}
+
+/// push parser turning a raw byte stream into events as complete lines arrive,
+/// for transports that do not deliver engine output line by line ( e.g. sockets ),
+/// lines that are not part of the protocol are banner events until the first protocol line
+#[derive(Debug, Clone, Default)]
+pub struct Parser {
+    /// bytes of the incomplete last line
+    pending: Vec<u8>,
+    /// whether a protocol line was received
+    started: bool,
+}
+
+/// parser implementation
+impl Parser {
+    /// create new parser
+    pub fn new() -> Self {
+        Self {
+            pending: vec![],
+            started: false,
+        }
+    }
+
+    /// feed a chunk of engine output, returns events for the lines it completes
+    ///
+    /// lines may end in \n or \r\n, empty lines are skipped, invalid utf-8 is replaced
+    /// and info lines failing to parse are returned as unknown events
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<UciEvent> {
+        let mut events = vec![];
+
+        for chunk in bytes.split_inclusive(|byte| *byte == b'\n') {
+            self.pending.extend_from_slice(chunk);
+
+            if chunk.ends_with(b"\n") {
+                events.extend(self.take_line());
+            }
+        }
+
+        events
+    }
+
+    /// end of stream, returns the event for the last line if it was not terminated
+    pub fn finish(&mut self) -> Option<UciEvent> {
+        self.take_line()
+    }
+
+    /// bytes received that do not form a complete line yet
+    pub fn pending(&self) -> &[u8] {
+        &self.pending
+    }
+
+    /// parse and clear pending line, None if it is empty
+    fn take_line(&mut self) -> Option<UciEvent> {
+        let line = String::from_utf8_lossy(&self.pending).trim().to_string();
+
+        self.pending.clear();
+
+        if line.is_empty() {
+            return None;
+        }
+
+        let event = match parse_line(&line) {
+            Ok(UciEvent::Unknown(line)) if !self.started => UciEvent::Banner(line),
+            Ok(event) => event,
+            _ => UciEvent::Unknown(line),
+        };
+
+        if !matches!(event, UciEvent::Banner(_)) {
+            self.started = true;
+        }
+
+        Some(event)
+    }
+}
+
+#[test]
+fn parse_protocol_lines() {
+    let events: Vec<String> = [
+        "id name Stockfish 16.1",
+        "option name Hash type spin default 16 min 1 max 33554432",
+        "uciok",
+        "readyok",
+        "registration checking",
+        "copyprotection ok",
+        "info string NNUE evaluation   enabled",
+        "bestmove e2e4 ponder e7e5",
+        "bestmove d2d4",
+        "bestmove (none)",
+        "Stockfish 16.1 by the Stockfish developers",
+    ]
+    .iter()
+    .map(|line| format!("{:?}", parse_line(line).unwrap()))
+    .collect();
+
+    assert_eq!(
+        events,
+        vec![
+            r#"Id { key: "name", value: "Stockfish 16.1" }"#,
+            r#"Option(UciOptionDef { name: "Hash", kind: Spin { default: Some(16), min: Some(1), max: Some(33554432) } })"#,
+            "UciOk",
+            "ReadyOk",
+            r#"Registration("checking")"#,
+            r#"CopyProtection("ok")"#,
+            r#"InfoString("NNUE evaluation   enabled")"#,
+            r#"BestMove(BestMove { bestmove: Some("e2e4"), ponder: Some("e7e5") })"#,
+            r#"BestMove(BestMove { bestmove: Some("d2d4"), ponder: None })"#,
+            r#"BestMove(BestMove { bestmove: None, ponder: None })"#,
+            r#"Unknown("Stockfish 16.1 by the Stockfish developers")"#,
+        ]
+    );
+}
+
+#[test]
+fn parse_bestmove() {
+    assert_eq!(
+        BestMove::parse("bestmove e2e4 ponder e7e5"),
+        Some(BestMove {
+            bestmove: Some("e2e4".to_string()),
+            ponder: Some("e7e5".to_string())
+        })
+    );
+
+    assert!(BestMove::parse("bestmove (none)").unwrap().is_none());
+    assert!(BestMove::parse("0000").unwrap().is_none());
+    assert_eq!(BestMove::parse("bestmove"), None);
+
+    // ponder keyword without move
+    assert_eq!(
+        BestMove::parse("bestmove e2e4 ponder").unwrap().ponder,
+        None
+    );
+}
+
+#[test]
+fn parse_info_event() {
+    match parse_line("info depth 12 score cp 30 pv e2e4").unwrap() {
+        UciEvent::Info(ai) => {
+            assert_eq!(ai.depth, 12);
+            assert_eq!(ai.bestmove(), Some("e2e4".to_string()));
+        }
+        event => panic!("unexpected event {:?}", event),
+    }
+
+    assert!(parse_line("info depth x").is_err());
+}
+
+#[test]
+fn parser_partial_chunks() {
+    let mut parser = Parser::new();
+
+    assert!(parser.feed(b"id name Sto").is_empty());
+    assert_eq!(parser.pending(), b"id name Sto");
+
+    let mut events = vec![];
+
+    for chunk in [
+        &b"ckfish\r\nuciok\r"[..],
+        b"\n\r\nreadyok\ninfo depth x\nbestm",
+        b"ove e2e4",
+    ] {
+        events.extend(parser.feed(chunk));
+    }
+
+    events.extend(parser.finish());
+
+    let events: Vec<String> = events.iter().map(|event| format!("{:?}", event)).collect();
+
+    assert_eq!(
+        events,
+        vec![
+            r#"Id { key: "name", value: "Stockfish" }"#,
+            "UciOk",
+            "ReadyOk",
+            r#"Unknown("info depth x")"#,
+            r#"BestMove(BestMove { bestmove: Some("e2e4"), ponder: None })"#,
+        ]
+    );
+
+    assert!(parser.pending().is_empty());
+    assert!(parser.finish().is_none());
+}
+
+#[test]
+fn parser_banner() {
+    let mut parser = Parser::new();
+
+    let events: Vec<String> = parser
+        .feed(b"Stockfish 16 by the Stockfish developers\nid name Stockfish 16\nhelp\n")
+        .iter()
+        .map(|event| format!("{:?}", event))
+        .collect();
+
+    assert_eq!(
+        events,
+        vec![
+            r#"Banner("Stockfish 16 by the Stockfish developers")"#,
+            r#"Id { key: "name", value: "Stockfish 16" }"#,
+            r#"Unknown("help")"#,
+        ]
+    );
+}
@@ -0,0 +1,411 @@
+//! C ABI for the info parser and the engine wrapper
+//!
+//! Strings returned by these functions are owned by the caller and must be
+//! released with `uciengine_string_free`. Every `*_new` function has a matching
+//! `*_free` function. The header `include/uciengine.h` is generated with cbindgen.
+
+use std::collections::VecDeque;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Arc;
+
+use log::debug;
+
+use tokio::runtime::Runtime;
+use tokio::sync::{broadcast, oneshot};
+
+use crate::analysis::*;
+use crate::uciengine::*;
+
+/// engine handle owning the runtime the engine tasks run on
+pub struct UciEngineHandle {
+    /// runtime driving the engine tasks
+    rt: Runtime,
+    /// engine
+    engine: Arc<UciEngine>,
+    /// analysis info receiver
+    arx: broadcast::Receiver<AnalysisInfo>,
+    /// pending go results in issue order
+    pending: VecDeque<oneshot::Receiver<GoResult>>,
+}
+
+/// convert C string to &str, None for null or invalid utf8
+unsafe fn to_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+
+    CStr::from_ptr(s).to_str().ok()
+}
+
+/// convert string to caller owned C string, null if it contains a nul byte
+fn to_c_string(s: String) -> *mut c_char {
+    match CString::new(s) {
+        Ok(s) => s.into_raw(),
+        _ => std::ptr::null_mut(),
+    }
+}
+
+/// apply a builder method to a go job in place
+unsafe fn update_job(job: *mut GoJob, f: impl FnOnce(GoJob) -> GoJob) -> c_int {
+    match job.as_mut() {
+        Some(job) => {
+            *job = f(std::mem::take(job));
+
+            0
+        }
+        _ => -1,
+    }
+}
+
+/// free a string returned by this library
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by this library that was not freed yet
+#[no_mangle]
+pub unsafe extern "C" fn uciengine_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// create new analysis info
+#[no_mangle]
+pub extern "C" fn uciengine_analysis_info_new() -> *mut AnalysisInfo {
+    Box::into_raw(Box::new(AnalysisInfo::new()))
+}
+
+/// free analysis info
+///
+/// # Safety
+///
+/// `ai` must be null or a pointer returned by `uciengine_analysis_info_new` that was not freed yet
+#[no_mangle]
+pub unsafe extern "C" fn uciengine_analysis_info_free(ai: *mut AnalysisInfo) {
+    if !ai.is_null() {
+        drop(Box::from_raw(ai));
+    }
+}
+
+/// parse info line into analysis info, returns 0 on success, -1 on failure
+///
+/// # Safety
+///
+/// `ai` must be a valid analysis info pointer, `line` a nul terminated string
+#[no_mangle]
+pub unsafe extern "C" fn uciengine_analysis_info_parse(
+    ai: *mut AnalysisInfo,
+    line: *const c_char,
+) -> c_int {
+    match (ai.as_mut(), to_str(line)) {
+        (Some(ai), Some(line)) => match ai.parse(line) {
+            Ok(_) => 0,
+            _ => -1,
+        },
+        _ => -1,
+    }
+}
+
+/// analysis info as json, null on failure
+///
+/// # Safety
+///
+/// `ai` must be a valid analysis info pointer
+#[no_mangle]
+pub unsafe extern "C" fn uciengine_analysis_info_to_json(ai: *const AnalysisInfo) -> *mut c_char {
+    match ai.as_ref().map(|ai| ai.to_json()) {
+        Some(Ok(json)) => to_c_string(json),
+        _ => std::ptr::null_mut(),
+    }
+}
+
+/// create new go job
+#[no_mangle]
+pub extern "C" fn uciengine_go_job_new() -> *mut GoJob {
+    Box::into_raw(Box::new(GoJob::new()))
+}
+
+/// free go job that was not passed to `uciengine_engine_go`
+///
+/// # Safety
+///
+/// `job` must be null or a pointer returned by `uciengine_go_job_new` that was not freed or consumed yet
+#[no_mangle]
+pub unsafe extern "C" fn uciengine_go_job_free(job: *mut GoJob) {
+    if !job.is_null() {
+        drop(Box::from_raw(job));
+    }
+}
+
+/// set position startpos, returns 0 on success, -1 on failure
+///
+/// # Safety
+///
+/// `job` must be a valid go job pointer
+#[no_mangle]
+pub unsafe extern "C" fn uciengine_go_job_pos_startpos(job: *mut GoJob) -> c_int {
+    update_job(job, |job| job.pos_startpos())
+}
+
+/// set position fen, returns 0 on success, -1 on failure
+///
+/// # Safety
+///
+/// `job` must be a valid go job pointer, `fen` a nul terminated string
+#[no_mangle]
+pub unsafe extern "C" fn uciengine_go_job_pos_fen(job: *mut GoJob, fen: *const c_char) -> c_int {
+    match to_str(fen) {
+        Some(fen) => update_job(job, |job| job.pos_fen(fen)),
+        _ => -1,
+    }
+}
+
+/// set position moves ( space separated uci moves ), returns 0 on success, -1 on failure
+///
+/// # Safety
+///
+/// `job` must be a valid go job pointer, `moves` a nul terminated string
+#[no_mangle]
+pub unsafe extern "C" fn uciengine_go_job_pos_moves(
+    job: *mut GoJob,
+    moves: *const c_char,
+) -> c_int {
+    match to_str(moves) {
+        Some(moves) => update_job(job, |job| job.pos_moves(moves)),
+        _ => -1,
+    }
+}
+
+/// set uci option, returns 0 on success, -1 on failure
+///
+/// # Safety
+///
+/// `job` must be a valid go job pointer, `key` and `value` nul terminated strings
+#[no_mangle]
+pub unsafe extern "C" fn uciengine_go_job_uci_opt(
+    job: *mut GoJob,
+    key: *const c_char,
+    value: *const c_char,
+) -> c_int {
+    match (to_str(key), to_str(value)) {
+        (Some(key), Some(value)) => update_job(job, |job| job.uci_opt(key, value)),
+        _ => -1,
+    }
+}
+
+/// set go option, returns 0 on success, -1 on failure
+///
+/// # Safety
+///
+/// `job` must be a valid go job pointer, `key` and `value` nul terminated strings
+#[no_mangle]
+pub unsafe extern "C" fn uciengine_go_job_go_opt(
+    job: *mut GoJob,
+    key: *const c_char,
+    value: *const c_char,
+) -> c_int {
+    match (to_str(key), to_str(value)) {
+        (Some(key), Some(value)) => update_job(job, |job| job.go_opt(key, value)),
+        _ => -1,
+    }
+}
+
+/// spawn engine, null on failure
+///
+/// # Safety
+///
+/// `path` must be a nul terminated string
+#[no_mangle]
+pub unsafe extern "C" fn uciengine_engine_new(path: *const c_char) -> *mut UciEngineHandle {
+    let path = match to_str(path) {
+        Some(path) => path,
+        _ => return std::ptr::null_mut(),
+    };
+
+    let rt = match Runtime::new() {
+        Ok(rt) => rt,
+        _ => return std::ptr::null_mut(),
+    };
+
+    // spawning panics if the engine cannot be started, which must not unwind into C
+    let engine = catch_unwind(AssertUnwindSafe(|| {
+        let _guard = rt.enter();
+
+        UciEngine::new(path)
+    }));
+
+    match engine {
+        Ok(engine) => {
+            let arx = engine.atx.subscribe();
+
+            Box::into_raw(Box::new(UciEngineHandle {
+                rt,
+                engine,
+                arx,
+                pending: VecDeque::new(),
+            }))
+        }
+        _ => std::ptr::null_mut(),
+    }
+}
+
+/// issue go job, the job is consumed, returns 0 on success, -1 on failure
+///
+/// # Safety
+///
+/// `engine` must be a valid engine handle, `job` a pointer returned by `uciengine_go_job_new`
+#[no_mangle]
+pub unsafe extern "C" fn uciengine_engine_go(
+    engine: *mut UciEngineHandle,
+    job: *mut GoJob,
+) -> c_int {
+    match engine.as_mut() {
+        Some(engine) if !job.is_null() => {
+            let job = *Box::from_raw(job);
+
+            let rrx = engine.engine.go(job);
+
+            engine.pending.push_back(rrx);
+
+            0
+        }
+        _ => -1,
+    }
+}
+
+/// latest analysis info as json, null if nothing new arrived since the last poll
+///
+/// # Safety
+///
+/// `engine` must be a valid engine handle
+#[no_mangle]
+pub unsafe extern "C" fn uciengine_engine_poll_info(engine: *mut UciEngineHandle) -> *mut c_char {
+    let engine = match engine.as_mut() {
+        Some(engine) => engine,
+        _ => return std::ptr::null_mut(),
+    };
+
+    let mut latest = None;
+
+    loop {
+        match engine.arx.try_recv() {
+            Ok(ai) => latest = Some(ai),
+            Err(broadcast::error::TryRecvError::Lagged(_)) => continue,
+            _ => break,
+        }
+    }
+
+    match latest.map(|ai| ai.to_json()) {
+        Some(Ok(json)) => to_c_string(json),
+        _ => std::ptr::null_mut(),
+    }
+}
+
+/// result of the oldest pending go job as json, null if it is not done yet
+///
+/// # Safety
+///
+/// `engine` must be a valid engine handle
+#[no_mangle]
+pub unsafe extern "C" fn uciengine_engine_poll_result(engine: *mut UciEngineHandle) -> *mut c_char {
+    let engine = match engine.as_mut() {
+        Some(engine) => engine,
+        _ => return std::ptr::null_mut(),
+    };
+
+    loop {
+        let result = match engine.pending.front_mut() {
+            Some(rrx) => rrx.try_recv(),
+            _ => return std::ptr::null_mut(),
+        };
+
+        match result {
+            Ok(go_result) => {
+                engine.pending.pop_front();
+
+                let json = serde_json::json!({
+                    "bestmove": go_result.bestmove,
+                    "ponder": go_result.ponder,
+                    "ai": go_result.ai.to_serde(),
+                });
+
+                return to_c_string(json.to_string());
+            }
+            Err(oneshot::error::TryRecvError::Closed) => {
+                // jobs that never produce a result ( ponder, custom ) are skipped
+                engine.pending.pop_front();
+            }
+            _ => return std::ptr::null_mut(),
+        }
+    }
+}
+
+/// quit engine and free engine handle
+///
+/// # Safety
+///
+/// `engine` must be null or a pointer returned by `uciengine_engine_new` that was not freed yet
+#[no_mangle]
+pub unsafe extern "C" fn uciengine_engine_free(engine: *mut UciEngineHandle) {
+    if engine.is_null() {
+        return;
+    }
+
+    let engine = Box::from_raw(engine);
+
+    let timeout = std::time::Duration::from_millis(1000);
+
+    let shutdown_result = engine
+        .rt
+        .block_on(tokio::time::timeout(timeout, engine.engine.shutdown()));
+
+    debug!("engine shutdown result {:?}", shutdown_result);
+
+    drop(engine.engine);
+
+    engine.rt.shutdown_timeout(timeout);
+}
+
+#[test]
+fn ffi_parse() {
+    let ai = uciengine_analysis_info_new();
+
+    let line = CString::new("info depth 5 score cp 17 pv e2e4").unwrap();
+
+    unsafe {
+        assert_eq!(uciengine_analysis_info_parse(ai, line.as_ptr()), 0);
+        assert_eq!((*ai).depth, 5);
+
+        let json = uciengine_analysis_info_to_json(ai);
+
+        assert!(CStr::from_ptr(json)
+            .to_str()
+            .unwrap()
+            .contains("\"depth\":5"));
+
+        uciengine_string_free(json);
+        uciengine_analysis_info_free(ai);
+    }
+}
+
+#[test]
+fn ffi_go_job() {
+    let job = uciengine_go_job_new();
+
+    let moves = CString::new("e2e4 e7e5").unwrap();
+
+    unsafe {
+        assert_eq!(uciengine_go_job_pos_startpos(job), 0);
+        assert_eq!(uciengine_go_job_pos_moves(job, moves.as_ptr()), 0);
+        assert_eq!(uciengine_go_job_pos_moves(job, std::ptr::null()), -1);
+
+        assert_eq!(
+            (*job).to_commands(),
+            vec!["position startpos moves e2e4 e7e5", "go"]
+        );
+
+        uciengine_go_job_free(job);
+    }
+}
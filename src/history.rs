@@ -0,0 +1,182 @@
+use crate::analysis::*;
+use crate::prelude::*;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// keeps the best line of every depth of a search, for eval versus depth plots
+/// and convergence checks
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AnalysisHistory {
+    /// analysis info of the best line per depth, shallowest first
+    depths: Vec<AnalysisInfo>,
+}
+
+/// analysis history implementation
+impl AnalysisHistory {
+    /// create new empty analysis history
+    pub fn new() -> Self {
+        Self { depths: vec![] }
+    }
+
+    /// analysis info per depth, shallowest first
+    pub fn depths(&self) -> &[AnalysisInfo] {
+        &self.depths
+    }
+
+    /// analysis info of given depth, None if not received
+    pub fn get(&self, depth: usize) -> Option<AnalysisInfo> {
+        self.depths.iter().find(|ai| ai.depth == depth).copied()
+    }
+
+    /// analysis info of the deepest depth, None if no depth was received yet
+    pub fn last(&self) -> Option<AnalysisInfo> {
+        self.depths.last().copied()
+    }
+
+    /// depth and score per depth, shallowest first
+    pub fn scores(&self) -> Vec<(usize, Score)> {
+        self.depths.iter().map(|ai| (ai.depth, ai.score)).collect()
+    }
+
+    /// number of depths
+    pub fn len(&self) -> usize {
+        self.depths.len()
+    }
+
+    /// whether no depth was received yet
+    pub fn is_empty(&self) -> bool {
+        self.depths.is_empty()
+    }
+
+    /// remove all depths
+    pub fn reset(&mut self) {
+        self.depths.clear();
+    }
+
+    /// push an engine output line, returns whether the line was stored
+    pub fn push<T: AsRef<str>>(&mut self, line: T) -> Result<bool, InfoParseError> {
+        let mut ai = AnalysisInfo::new();
+
+        ai.parse(line)?;
+
+        Ok(self.insert(ai))
+    }
+
+    /// store analysis info parsed elsewhere, returns whether it was stored
+    ///
+    /// only the best line counts and it must carry a pv, a later line of the same depth replaces
+    /// the stored one unless it would replace an exact score with a bound,
+    /// a shallower depth than the last one starts over as it belongs to a new search
+    pub fn insert(&mut self, ai: AnalysisInfo) -> bool {
+        if (ai.multipv > 1) || ai.pv().is_none() {
+            return false;
+        }
+
+        match self.depths.last_mut() {
+            Some(last) if ai.depth < last.depth => self.depths.clear(),
+            Some(last) if ai.depth == last.depth => {
+                if last.is_exact() && !ai.is_exact() {
+                    return false;
+                }
+
+                *last = ai;
+
+                return true;
+            }
+            _ => (),
+        }
+
+        self.depths.push(ai);
+
+        true
+    }
+
+    /// whether the last depths agree on the best move and their exact scores stay
+    /// within margin centipawns of each other, false if fewer depths were received
+    pub fn is_converged(&self, depths: usize, margin_cp: i64) -> bool {
+        let depths = depths.max(1);
+
+        if self.depths.len() < depths {
+            return false;
+        }
+
+        let last = &self.depths[(self.depths.len() - depths)..];
+
+        if !last.iter().all(|ai| ai.is_exact()) {
+            return false;
+        }
+
+        let bestmove = last[0].bestmove();
+
+        if last.iter().any(|ai| ai.bestmove() != bestmove) {
+            return false;
+        }
+
+        let scores = last.iter().map(|ai| ai.score.score_value());
+
+        match (scores.clone().min(), scores.max()) {
+            (Some(min), Some(max)) => (max - min) <= margin_cp,
+            _ => false,
+        }
+    }
+
+    /// history with scores from the point of view of white, side is the side to move
+    pub fn to_white_pov(&self, side: Color) -> Self {
+        Self {
+            depths: self.depths.iter().map(|ai| ai.to_white_pov(side)).collect(),
+        }
+    }
+}
+
+#[test]
+fn history_per_depth() {
+    let mut history = AnalysisHistory::new();
+
+    for line in [
+        "info depth 1 score cp 20 pv e2e4",
+        "info depth 2 score cp 35 pv d2d4",
+        "info depth 2 multipv 2 score cp 10 pv e2e4",
+        "info depth 3 currmove e2e4 currmovenumber 1",
+        "info depth 3 score cp 30 pv e2e4 e7e5",
+        "info depth 3 score cp 60 lowerbound pv e2e4",
+        "info depth 3 score cp 28 pv e2e4 c7c5",
+    ] {
+        history.push(line).unwrap();
+    }
+
+    assert_eq!(
+        history.scores(),
+        vec![(1, Score::Cp(20)), (2, Score::Cp(35)), (3, Score::Cp(28))]
+    );
+    assert_eq!(history.get(2).unwrap().bestmove(), Some("d2d4".to_string()));
+    assert_eq!(history.last().unwrap().pv_moves(), vec!["e2e4", "c7c5"]);
+
+    // a new search starts over
+    assert!(history.push("info depth 1 score cp -10 pv g1f3").unwrap());
+    assert_eq!(history.len(), 1);
+}
+
+#[test]
+fn history_convergence() {
+    let mut history = AnalysisHistory::new();
+
+    for line in [
+        "info depth 8 score cp 90 pv d2d4",
+        "info depth 9 score cp 30 pv e2e4",
+        "info depth 10 score cp 42 pv e2e4",
+        "info depth 11 score cp 35 pv e2e4",
+    ] {
+        history.push(line).unwrap();
+    }
+
+    assert!(history.is_converged(3, 15));
+    assert!(!history.is_converged(3, 10));
+    assert!(!history.is_converged(4, 100));
+    assert!(!history.is_converged(5, 100));
+
+    let history = history.to_white_pov(Color::Black);
+
+    assert_eq!(history.last().unwrap().score, Score::Cp(-35));
+}
@@ -2,11 +2,16 @@
 //! # Examples
 //!
 //!
-//!```
+//!```no_run
 //!extern crate env_logger;
 //!
+//!# #[cfg(feature = "std")]
 //!use uciengine::uciengine::*;
 //!
+//!# // the engine wrapper needs std, without it the example compiles to nothing
+//!# #[cfg(not(feature = "std"))]
+//!# fn main() {}
+//!# #[cfg(feature = "std")]
 //!#[tokio::main]
 //!async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!    env_logger::init();
@@ -50,16 +55,42 @@
 //!    // wait enough for the go commands to complete in the background
 //!    tokio::time::sleep(tokio::time::Duration::from_millis(20000)).await;
 //!
-//!    // quit engine
-//!    engine.quit();
-//!
-//!    // wait for engine to quit gracefully
-//!    tokio::time::sleep(tokio::time::Duration::from_millis(3000)).await;
+//!    // quit engine and wait for it to exit
+//!    engine.shutdown().await;
 //!
 //!    Ok(())
 //!}
 //!```
 
+//! # Features
+//!
+//! The `std` feature ( enabled by default ) provides the engine wrapper in [`uciengine`].
+//! Without it the crate is `no_std` and only needs `alloc`, leaving the info, event and option parsers,
+//! the accumulator, the multipv collector, the analysis history, the trend tracker and the phase classifier
+//! available for targets that receive engine output by other means.
+//!
+//! The `serde` feature ( enabled by default ) implements `Serialize` and `Deserialize` for analysis infos,
+//! scores, parsed events and options and the go job types, and provides the json conversions of analysis infos.
+//!
+//! The `ffi` feature ( implies `serde` ) exposes a C ABI in `ffi`, declared in `include/uciengine.h`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 // lib
+pub mod accumulator;
 pub mod analysis;
+pub mod event;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod history;
+#[cfg(feature = "std")]
+pub mod mock;
+pub mod multipv;
+pub mod options;
+pub mod phase;
+mod prelude;
+pub mod trend;
+#[cfg(feature = "std")]
 pub mod uciengine;
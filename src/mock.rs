@@ -0,0 +1,353 @@
+//! scriptable mock engine for testing code built on top of this crate
+//!
+//! ```
+//! use uciengine::mock::*;
+//! use uciengine::uciengine::*;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let engine = MockEngine::new()
+//!         .on_go(vec![
+//!             MockStep::line("info depth 1 score cp 20 pv e2e4"),
+//!             MockStep::line("bestmove e2e4"),
+//!         ])
+//!         .on_go(vec![MockStep::Crash])
+//!         .spawn();
+//!
+//!     let go_result = engine.go(GoJob::new().pos_startpos()).await.unwrap();
+//!
+//!     assert_eq!(go_result.bestmove, Some("e2e4".to_string()));
+//!
+//!     // the engine crashes instead of answering
+//!     assert!(engine.go(GoJob::new().pos_startpos()).await.is_err());
+//! }
+//! ```
+
+use log::{debug, log_enabled, Level};
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream};
+
+use crate::uciengine::UciEngine;
+
+/// capacity of the in memory pipe between engine and mock
+const MOCK_PIPE_CAPACITY: usize = 64 * 1024;
+
+/// single step of a scripted mock engine response
+#[derive(Debug, Clone)]
+pub enum MockStep {
+    /// write a line of output
+    Line(String),
+    /// wait before the next step
+    Delay(Duration),
+    /// close the connection as if the engine process crashed
+    Crash,
+    /// stop responding to anything until input is closed
+    Hang,
+}
+
+/// mock step implementation
+impl MockStep {
+    /// line step from anything that can be displayed
+    pub fn line<T: core::fmt::Display>(line: T) -> Self {
+        MockStep::Line(format!("{}", line))
+    }
+
+    /// delay step in milliseconds
+    pub fn delay_ms(ms: u64) -> Self {
+        MockStep::Delay(Duration::from_millis(ms))
+    }
+}
+
+/// outcome of running a script
+enum ScriptOutcome {
+    /// keep serving commands
+    Continue,
+    /// stop responding
+    Hang,
+    /// close the connection
+    Exit,
+}
+
+/// mock engine answering go commands with scripted responses
+///
+/// go commands are answered with the scripts registered by `on_go`, in order,
+/// once scripts run out they are answered with `bestmove 0000`;
+/// banner lines are written on startup;
+/// scripts for `go ponder` and `go infinite` run on the following ponderhit or stop;
+/// uci and isready are answered with uciok and readyok, quit ends the mock;
+/// registration is checked after uciok if required
+#[derive(Debug, Clone)]
+pub struct MockEngine {
+    /// engine name reported on uci
+    name: String,
+    /// lines written on startup, before any command
+    banner: Vec<String>,
+    /// answer uci with uciok
+    uciok: bool,
+    /// option declarations sent on uci
+    options: Vec<String>,
+    /// registration code required after uciok
+    registration_code: Option<String>,
+    /// scripts answering go commands
+    go_scripts: VecDeque<Vec<MockStep>>,
+}
+
+/// default mock engine
+impl Default for MockEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// mock engine implementation
+impl MockEngine {
+    /// create new mock engine without scripts
+    pub fn new() -> Self {
+        Self {
+            name: "MockEngine".to_string(),
+            banner: vec![],
+            uciok: true,
+            options: vec![],
+            registration_code: None,
+            go_scripts: VecDeque::new(),
+        }
+    }
+
+    /// set engine name reported on uci and return self
+    pub fn name<T: core::fmt::Display>(mut self, name: T) -> Self {
+        self.name = format!("{}", name);
+
+        self
+    }
+
+    /// add line written on startup and return self
+    pub fn banner<T: core::fmt::Display>(mut self, line: T) -> Self {
+        self.banner.push(format!("{}", line));
+
+        self
+    }
+
+    /// add option declaration sent on uci and return self
+    /// ( e.g. name Hash type spin default 16 min 1 max 1024 )
+    pub fn option<T: core::fmt::Display>(mut self, option: T) -> Self {
+        self.options.push(format!("option {}", option));
+
+        self
+    }
+
+    /// require registration with the given code after uciok and return self,
+    /// like commercial engines reporting registration error until registered
+    pub fn require_registration<T: core::fmt::Display>(mut self, code: T) -> Self {
+        self.registration_code = Some(format!("{}", code));
+
+        self
+    }
+
+    /// answer uci without uciok and return self,
+    /// like minimal engines that skip parts of the handshake
+    pub fn skip_uciok(mut self) -> Self {
+        self.uciok = false;
+
+        self
+    }
+
+    /// add script answering the next unanswered go command and return self
+    pub fn on_go(mut self, steps: Vec<MockStep>) -> Self {
+        self.go_scripts.push_back(steps);
+
+        self
+    }
+
+    /// spawn mock and return uci engine connected to it,
+    /// the mock task is owned by the engine
+    pub fn spawn(self) -> std::sync::Arc<UciEngine> {
+        let (engine_side, mock_side) = tokio::io::duplex(MOCK_PIPE_CAPACITY);
+
+        let (reader, writer) = tokio::io::split(engine_side);
+
+        let mock_task = tokio::spawn(self.run(mock_side));
+
+        UciEngine::start(reader, writer, vec![mock_task])
+    }
+
+    /// serve engine commands until input is closed or the script ends the mock
+    async fn run(mut self, stream: DuplexStream) {
+        let (reader, mut writer) = tokio::io::split(stream);
+
+        let mut lines = BufReader::new(reader).lines();
+
+        let mut pending: Option<Vec<MockStep>> = None;
+
+        let mut hung = false;
+
+        let banner = self.banner.iter().map(MockStep::line).collect();
+
+        if let ScriptOutcome::Exit = Self::run_script(&mut writer, banner).await {
+            return;
+        }
+
+        while let Ok(Some(line)) = lines.next_line().await {
+            if log_enabled!(Level::Debug) {
+                debug!("mock engine in : {}", line);
+            }
+
+            if hung {
+                continue;
+            }
+
+            let mut tokens = line.split_whitespace();
+
+            let steps = match tokens.next() {
+                Some("uci") => {
+                    let mut steps = vec![MockStep::line(format!("id name {}", self.name))];
+
+                    steps.extend(self.options.iter().map(MockStep::line));
+
+                    if self.uciok {
+                        steps.push(MockStep::line("uciok"));
+                    }
+
+                    if self.registration_code.is_some() {
+                        steps.push(MockStep::line("registration checking"));
+                        steps.push(MockStep::line("registration error"));
+                    }
+
+                    steps
+                }
+                Some("register") => {
+                    let code = line
+                        .split_whitespace()
+                        .skip_while(|token| *token != "code")
+                        .nth(1);
+
+                    match (&self.registration_code, code) {
+                        (Some(required), Some(code)) if required == code => vec![
+                            MockStep::line("registration checking"),
+                            MockStep::line("registration ok"),
+                        ],
+                        (Some(_), Some(_)) => vec![
+                            MockStep::line("registration checking"),
+                            MockStep::line("registration error"),
+                        ],
+                        // register later
+                        _ => vec![],
+                    }
+                }
+                Some("isready") => vec![MockStep::line("readyok")],
+                Some("go") => {
+                    let steps = self
+                        .go_scripts
+                        .pop_front()
+                        .unwrap_or_else(|| vec![MockStep::line("bestmove 0000")]);
+
+                    if tokens.any(|token| token == "ponder" || token == "infinite") {
+                        pending = Some(steps);
+
+                        vec![]
+                    } else {
+                        steps
+                    }
+                }
+                Some("ponderhit") | Some("stop") => pending.take().unwrap_or_default(),
+                Some("quit") => return,
+                _ => vec![],
+            };
+
+            match Self::run_script(&mut writer, steps).await {
+                ScriptOutcome::Continue => (),
+                ScriptOutcome::Hang => hung = true,
+                ScriptOutcome::Exit => return,
+            }
+        }
+    }
+
+    /// run script steps
+    async fn run_script<W>(writer: &mut W, steps: Vec<MockStep>) -> ScriptOutcome
+    where
+        W: AsyncWriteExt + Unpin,
+    {
+        for step in steps {
+            match step {
+                MockStep::Line(line) => {
+                    if writer
+                        .write_all(format!("{}\n", line).as_bytes())
+                        .await
+                        .is_err()
+                    {
+                        return ScriptOutcome::Exit;
+                    }
+                }
+                MockStep::Delay(duration) => tokio::time::sleep(duration).await,
+                MockStep::Crash => return ScriptOutcome::Exit,
+                MockStep::Hang => return ScriptOutcome::Hang,
+            }
+        }
+
+        ScriptOutcome::Continue
+    }
+}
+
+#[tokio::test]
+async fn scripted_go() {
+    use crate::uciengine::GoJob;
+
+    let engine = MockEngine::new()
+        .on_go(vec![
+            MockStep::line("info depth 1 score cp 20 pv e2e4"),
+            MockStep::delay_ms(10),
+            MockStep::line("info depth 2 score cp 35 pv d2d4 d7d5"),
+            MockStep::line("bestmove d2d4 ponder d7d5"),
+        ])
+        .spawn();
+
+    let go_result = engine.go(GoJob::new().pos_startpos()).await.unwrap();
+
+    assert_eq!(go_result.bestmove, Some("d2d4".to_string()));
+    assert_eq!(go_result.ponder, Some("d7d5".to_string()));
+    assert_eq!(
+        go_result.best_move().unwrap().ponder,
+        Some("d7d5".to_string())
+    );
+    assert_eq!(go_result.ai.depth, 2);
+
+    // scripts ran out
+    let go_result = engine.go(GoJob::new().pos_startpos()).await.unwrap();
+
+    assert_eq!(go_result.bestmove, Some("0000".to_string()));
+    assert!(go_result.best_move().unwrap().is_none());
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn scripted_crash() {
+    use crate::uciengine::GoJob;
+
+    let engine = MockEngine::new()
+        .on_go(vec![MockStep::line("info depth 1"), MockStep::Crash])
+        .spawn();
+
+    assert!(engine.go(GoJob::new().pos_startpos()).await.is_err());
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn scripted_ponder() {
+    use crate::uciengine::GoJob;
+
+    let engine = MockEngine::new()
+        .on_go(vec![MockStep::line("bestmove e7e5 ponder g1f3")])
+        .spawn();
+
+    drop(engine.go(GoJob::new().pos_startpos().pos_moves("e2e4").ponder()));
+
+    let go_result = engine.go(GoJob::new().ponderhit()).await.unwrap();
+
+    assert_eq!(go_result.bestmove, Some("e7e5".to_string()));
+
+    engine.shutdown().await;
+}
@@ -0,0 +1,141 @@
+use crate::analysis::*;
+use crate::prelude::*;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// collects the info lines of a multipv search into one analysis info per line,
+/// ranked by multipv index
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MultiPvAnalysis {
+    /// analysis info per line, best line first
+    lines: Vec<AnalysisInfo>,
+}
+
+/// multipv analysis implementation
+impl MultiPvAnalysis {
+    /// create new empty multipv analysis
+    pub fn new() -> Self {
+        Self { lines: vec![] }
+    }
+
+    /// ranked lines, best line first
+    pub fn lines(&self) -> &[AnalysisInfo] {
+        &self.lines
+    }
+
+    /// line with given rank ( 1 is the best line ), None if not received yet
+    pub fn get(&self, rank: usize) -> Option<AnalysisInfo> {
+        self.lines.iter().find(|ai| Self::rank(ai) == rank).copied()
+    }
+
+    /// best line, None if no line was received yet
+    pub fn best(&self) -> Option<AnalysisInfo> {
+        self.lines.first().copied()
+    }
+
+    /// number of lines
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// whether no line was received yet
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// remove all lines
+    pub fn reset(&mut self) {
+        self.lines.clear();
+    }
+
+    /// push an engine output line, returns whether the line was stored
+    ///
+    /// only info lines carrying a pv are stored, lines without multipv count as line 1
+    pub fn push<T: AsRef<str>>(&mut self, line: T) -> Result<bool, InfoParseError> {
+        let mut ai = AnalysisInfo::new();
+
+        ai.parse(line)?;
+
+        Ok(self.insert(ai))
+    }
+
+    /// store analysis info parsed elsewhere, replacing the line with the same rank,
+    /// returns whether the analysis info was stored ( it must carry a pv )
+    pub fn insert(&mut self, ai: AnalysisInfo) -> bool {
+        if ai.pv().is_none() {
+            return false;
+        }
+
+        let rank = Self::rank(&ai);
+
+        match self.lines.binary_search_by_key(&rank, Self::rank) {
+            Ok(index) => self.lines[index] = ai,
+            Err(index) => self.lines.insert(index, ai),
+        }
+
+        true
+    }
+
+    /// rank of analysis info, engines may omit multipv when only one line is searched
+    fn rank(ai: &AnalysisInfo) -> usize {
+        ai.multipv.max(1)
+    }
+}
+
+#[test]
+fn multipv_ranked_lines() {
+    let mut mpv = MultiPvAnalysis::new();
+
+    assert!(mpv
+        .push("info depth 10 multipv 2 score cp 15 pv d2d4 d7d5")
+        .unwrap());
+    assert!(mpv
+        .push("info depth 10 multipv 1 score cp 30 pv e2e4 e7e5")
+        .unwrap());
+    assert!(mpv
+        .push("info depth 10 multipv 3 score cp -5 pv g1f3")
+        .unwrap());
+
+    let bestmoves: Vec<Option<String>> = mpv.lines().iter().map(|ai| ai.bestmove()).collect();
+
+    assert_eq!(
+        bestmoves,
+        vec![
+            Some("e2e4".to_string()),
+            Some("d2d4".to_string()),
+            Some("g1f3".to_string())
+        ]
+    );
+
+    assert_eq!(mpv.get(3).unwrap().bestmove(), Some("g1f3".to_string()));
+    assert!(mpv.get(4).is_none());
+}
+
+#[test]
+fn multipv_replaces_line() {
+    let mut mpv = MultiPvAnalysis::new();
+
+    mpv.push("info depth 10 multipv 1 score cp 30 pv e2e4 e7e5")
+        .unwrap();
+    mpv.push("info depth 11 multipv 1 score cp 25 pv d2d4 d7d5")
+        .unwrap();
+
+    assert_eq!(mpv.len(), 1);
+    assert_eq!(mpv.best().unwrap().depth, 11);
+    assert_eq!(mpv.best().unwrap().bestmove(), Some("d2d4".to_string()));
+}
+
+#[test]
+fn multipv_ignores_lines_without_pv() {
+    let mut mpv = MultiPvAnalysis::new();
+
+    assert!(!mpv.push("info nodes 5000 nps 1000").unwrap());
+    assert!(!mpv.push("bestmove e2e4").unwrap());
+
+    // without multipv the line is line 1
+    assert!(mpv.push("info depth 5 score cp 10 pv e2e4").unwrap());
+
+    assert_eq!(mpv.get(1).unwrap().depth, 5);
+}
@@ -0,0 +1,487 @@
+use crate::prelude::*;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// option keywords, each followed by a value that may span several tokens
+const OPTION_KEYWORDS: [&str; 6] = ["name", "type", "default", "min", "max", "var"];
+
+/// type of an engine option with its defaults, ranges and combo vars
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum UciOptionKind {
+    /// boolean option
+    Check {
+        /// default value
+        default: Option<bool>,
+    },
+    /// integer option within a range
+    Spin {
+        /// default value
+        default: Option<i64>,
+        /// minimum value
+        min: Option<i64>,
+        /// maximum value
+        max: Option<i64>,
+    },
+    /// option with one of several predefined values
+    Combo {
+        /// default value
+        default: Option<String>,
+        /// allowed values
+        vars: Vec<String>,
+    },
+    /// option without value, setting it triggers an action ( e.g. Clear Hash )
+    Button,
+    /// text option
+    String {
+        /// default value ( empty for `<empty>` )
+        default: Option<String>,
+    },
+}
+
+/// engine option declared on uci
+/// ( option name Hash type spin default 16 min 1 max 33554432 )
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UciOptionDef {
+    /// option name, may contain spaces
+    pub name: String,
+    /// option type
+    pub kind: UciOptionKind,
+}
+
+/// option type implementation
+impl UciOptionKind {
+    /// default value as sent in setoption, None for buttons and options declared without default
+    pub fn default_value(&self) -> Option<String> {
+        match self {
+            UciOptionKind::Check { default } => default.map(|default| format!("{}", default)),
+            UciOptionKind::Spin { default, .. } => default.map(|default| format!("{}", default)),
+            UciOptionKind::Combo { default, .. } | UciOptionKind::String { default } => {
+                default.clone()
+            }
+            UciOptionKind::Button => None,
+        }
+    }
+}
+
+/// uci option declaration implementation
+impl UciOptionDef {
+    /// parse option declaration, with or without the leading option token,
+    /// None if name or type are missing or the type is unknown
+    pub fn parse<T: AsRef<str>>(line: T) -> Option<Self> {
+        let mut tokens = line.as_ref().split_whitespace().peekable();
+
+        if tokens.peek() == Some(&"option") {
+            tokens.next();
+        }
+
+        let mut values: Vec<(&str, String)> = vec![];
+
+        for token in tokens {
+            match values.last_mut() {
+                _ if OPTION_KEYWORDS.contains(&token) => values.push((token, String::new())),
+                Some((_, value)) => {
+                    if !value.is_empty() {
+                        value.push(' ');
+                    }
+
+                    value.push_str(token);
+                }
+                // value without keyword
+                _ => return None,
+            }
+        }
+
+        let value = |keyword: &str| {
+            values
+                .iter()
+                .find(|(key, _)| *key == keyword)
+                .map(|(_, value)| value.to_string())
+        };
+
+        let number = |keyword: &str| value(keyword).and_then(|value| value.parse::<i64>().ok());
+
+        let kind = match value("type")?.as_str() {
+            "check" => UciOptionKind::Check {
+                default: value("default").and_then(|value| value.parse::<bool>().ok()),
+            },
+            "spin" => UciOptionKind::Spin {
+                default: number("default"),
+                min: number("min"),
+                max: number("max"),
+            },
+            "combo" => UciOptionKind::Combo {
+                default: value("default"),
+                vars: values
+                    .iter()
+                    .filter(|(key, _)| *key == "var")
+                    .map(|(_, value)| value.to_string())
+                    .collect(),
+            },
+            "button" => UciOptionKind::Button,
+            "string" => UciOptionKind::String {
+                default: value("default").map(|value| match value.as_str() {
+                    "<empty>" => String::new(),
+                    _ => value,
+                }),
+            },
+            _ => return None,
+        };
+
+        match value("name") {
+            Some(name) if !name.is_empty() => Some(Self { name, kind }),
+            _ => None,
+        }
+    }
+}
+
+/// option names engines use for common settings, the unified key first
+/// ( compared case insensitively, so MultiPv matches MultiPV )
+const DEFAULT_OPTION_ALIASES: [&[&str]; 9] = [
+    &["Hash", "Hash Size", "HashSize"],
+    &["Threads", "Cores", "Max CPUs", "CPUs"],
+    &["MultiPV", "Multi PV"],
+    &["Ponder", "UCI_Ponder"],
+    &[
+        "SyzygyPath",
+        "TbPath",
+        "TablebasePath",
+        "Syzygy Path",
+        "SyzygyTablebasePath",
+    ],
+    &[
+        "Move Overhead",
+        "MoveOverhead",
+        "Move_Overhead",
+        "MoveTimeOverhead",
+    ],
+    &["Chess960", "UCI_Chess960"],
+    &["LimitStrength", "UCI_LimitStrength"],
+    &["Elo", "UCI_Elo"],
+];
+
+/// table mapping unified option keys to the names engines use for them
+#[derive(Debug, Clone)]
+pub struct OptionAliases {
+    /// unified key and the option names it may be declared as
+    aliases: Vec<(String, Vec<String>)>,
+}
+
+/// default option aliases
+impl Default for OptionAliases {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// option aliases implementation
+impl OptionAliases {
+    /// create new option aliases with the built in table
+    pub fn new() -> Self {
+        let aliases = DEFAULT_OPTION_ALIASES
+            .iter()
+            .map(|names| {
+                (
+                    names[0].to_string(),
+                    names.iter().map(|name| name.to_string()).collect(),
+                )
+            })
+            .collect();
+
+        Self { aliases }
+    }
+
+    /// add option name for unified key and return self, names added
+    /// are tried before the built in ones
+    pub fn alias<K, N>(mut self, key: K, name: N) -> Self
+    where
+        K: core::fmt::Display,
+        N: core::fmt::Display,
+    {
+        let key = format!("{}", key);
+        let name = format!("{}", name);
+
+        match self
+            .aliases
+            .iter_mut()
+            .find(|(unified, _)| unified.eq_ignore_ascii_case(&key))
+        {
+            Some((_, names)) => names.insert(0, name),
+            _ => self.aliases.push((key, vec![name])),
+        }
+
+        self
+    }
+
+    /// name under which the engine declares the option for unified key,
+    /// None if it declares none of the names, keys without aliases match themselves
+    pub fn resolve<'a>(&self, key: &str, declared: &'a [UciOptionDef]) -> Option<&'a str> {
+        let key_only = [key.to_string()];
+
+        let names = self
+            .aliases
+            .iter()
+            .find(|(unified, _)| unified.eq_ignore_ascii_case(key))
+            .map_or(&key_only[..], |(_, names)| &names[..]);
+
+        names.iter().find_map(|name| {
+            declared
+                .iter()
+                .find(|def| def.name.eq_ignore_ascii_case(name))
+                .map(|def| def.name.as_str())
+        })
+    }
+}
+
+/// how the engine should score draws
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DrawPolicy {
+    /// restore the declared defaults of the draw options
+    Default,
+    /// no contempt, draws score zero for both sides, for fair testing
+    Neutral,
+    /// contempt in centipawns, positive values make the engine avoid draws
+    Contempt(i64),
+}
+
+/// spin options scoring draws, by name ( compared case insensitively ),
+/// with the sign of contempt in their values
+/// ( Stockfish up to 11, Komodo, Houdini : Contempt, Lc0 : DrawScore... )
+const DRAW_SPIN_OPTIONS: [(&str, i64); 5] = [
+    ("Contempt", 1),
+    ("DrawScore", -1),
+    ("Draw Score", -1),
+    ("DrawScoreSideToMove", -1),
+    ("DrawScoreOpponent", 1),
+];
+
+/// combo options selecting the sides contempt applies to ( Stockfish Analysis Contempt )
+const DRAW_COMBO_OPTIONS: [&str; 1] = ["Analysis Contempt"];
+
+/// draw policy implementation
+impl DrawPolicy {
+    /// option values implementing the policy, as name value pairs,
+    /// for the draw options among the declared ones, spin values are clamped to range
+    pub fn options(self, declared: &[UciOptionDef]) -> Vec<(String, String)> {
+        let mut options = vec![];
+
+        for def in declared {
+            let known = |name: &&str| name.eq_ignore_ascii_case(&def.name);
+
+            let value = match &def.kind {
+                UciOptionKind::Spin { default, min, max } => {
+                    let sign = match DRAW_SPIN_OPTIONS.iter().find(|(name, _)| known(name)) {
+                        Some((_, sign)) => *sign,
+                        _ => continue,
+                    };
+
+                    let value = match self {
+                        DrawPolicy::Default => match default {
+                            Some(default) => *default,
+                            _ => continue,
+                        },
+                        DrawPolicy::Neutral => 0,
+                        DrawPolicy::Contempt(contempt) => contempt.saturating_mul(sign),
+                    };
+
+                    let value = value
+                        .max(min.unwrap_or(i64::MIN))
+                        .min(max.unwrap_or(i64::MAX));
+
+                    format!("{}", value)
+                }
+                UciOptionKind::Combo { default, vars } if DRAW_COMBO_OPTIONS.iter().any(known) => {
+                    let var = match self {
+                        DrawPolicy::Default => default.as_deref(),
+                        DrawPolicy::Neutral => Some("Off"),
+                        DrawPolicy::Contempt(_) => Some("Both"),
+                    };
+
+                    match var {
+                        Some(var) if vars.iter().any(|v| v == var) => var.to_string(),
+                        _ => continue,
+                    }
+                }
+                _ => continue,
+            };
+
+            options.push((def.name.to_string(), value));
+        }
+
+        options
+    }
+}
+
+#[test]
+fn parse_option_defs() {
+    assert_eq!(
+        UciOptionDef::parse("option name Hash type spin default 16 min 1 max 33554432"),
+        Some(UciOptionDef {
+            name: "Hash".to_string(),
+            kind: UciOptionKind::Spin {
+                default: Some(16),
+                min: Some(1),
+                max: Some(33554432)
+            }
+        })
+    );
+
+    assert_eq!(
+        UciOptionDef::parse("option name UCI_Chess960 type check default false"),
+        Some(UciOptionDef {
+            name: "UCI_Chess960".to_string(),
+            kind: UciOptionKind::Check {
+                default: Some(false)
+            }
+        })
+    );
+
+    assert_eq!(
+        UciOptionDef::parse("name Clear Hash type button"),
+        Some(UciOptionDef {
+            name: "Clear Hash".to_string(),
+            kind: UciOptionKind::Button
+        })
+    );
+
+    assert_eq!(
+        UciOptionDef::parse("option name SyzygyPath type string default <empty>"),
+        Some(UciOptionDef {
+            name: "SyzygyPath".to_string(),
+            kind: UciOptionKind::String {
+                default: Some(String::new())
+            }
+        })
+    );
+
+    assert_eq!(
+        UciOptionDef::parse(
+            "option name Style type combo default Normal var Solid var Normal var Risky Play"
+        ),
+        Some(UciOptionDef {
+            name: "Style".to_string(),
+            kind: UciOptionKind::Combo {
+                default: Some("Normal".to_string()),
+                vars: vec![
+                    "Solid".to_string(),
+                    "Normal".to_string(),
+                    "Risky Play".to_string()
+                ]
+            }
+        })
+    );
+}
+
+#[test]
+fn option_default_values() {
+    let defaults: Vec<Option<String>> = [
+        "option name Ponder type check default false",
+        "option name Hash type spin default 16 min 1 max 1024",
+        "option name Style type combo default Normal var Solid var Normal",
+        "option name SyzygyPath type string default <empty>",
+        "option name Clear Hash type button",
+    ]
+    .iter()
+    .map(|line| UciOptionDef::parse(line).unwrap().kind.default_value())
+    .collect();
+
+    assert_eq!(
+        defaults,
+        vec![
+            Some("false".to_string()),
+            Some("16".to_string()),
+            Some("Normal".to_string()),
+            Some(String::new()),
+            None
+        ]
+    );
+}
+
+#[test]
+fn parse_invalid_option_defs() {
+    assert_eq!(UciOptionDef::parse("option name Hash"), None);
+    assert_eq!(UciOptionDef::parse("option type spin"), None);
+    assert_eq!(UciOptionDef::parse("option name X type slider"), None);
+    assert_eq!(UciOptionDef::parse("option Hash type spin"), None);
+}
+
+#[test]
+fn draw_policy_options() {
+    let declared: Vec<UciOptionDef> = [
+        "option name Hash type spin default 16 min 1 max 33554432",
+        "option name Contempt type spin default 24 min -100 max 100",
+        "option name Analysis Contempt type combo default Both var Off var White var Black var Both",
+        "option name DrawScoreOpponent type spin default 0 min -100 max 100",
+        "option name DrawScoreSideToMove type spin default 0 min -100 max 100",
+    ]
+    .iter()
+    .filter_map(UciOptionDef::parse)
+    .collect();
+
+    let pairs = |policy: DrawPolicy| -> Vec<String> {
+        policy
+            .options(&declared)
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect()
+    };
+
+    assert_eq!(
+        pairs(DrawPolicy::Neutral),
+        vec![
+            "Contempt=0",
+            "Analysis Contempt=Off",
+            "DrawScoreOpponent=0",
+            "DrawScoreSideToMove=0"
+        ]
+    );
+
+    assert_eq!(
+        pairs(DrawPolicy::Contempt(150)),
+        vec![
+            "Contempt=100",
+            "Analysis Contempt=Both",
+            "DrawScoreOpponent=100",
+            "DrawScoreSideToMove=-100"
+        ]
+    );
+
+    assert_eq!(pairs(DrawPolicy::Default)[0], "Contempt=24");
+
+    assert_eq!(
+        pairs(DrawPolicy::Contempt(i64::MIN))[3],
+        "DrawScoreSideToMove=100"
+    );
+
+    assert!(DrawPolicy::Neutral.options(&declared[0..1]).is_empty());
+}
+
+#[test]
+fn option_aliases() {
+    let declared: Vec<UciOptionDef> = [
+        "option name TbPath type string default <empty>",
+        "option name MultiPv type spin default 1 min 1 max 64",
+        "option name UCI_Elo type spin default 1500 min 1000 max 2800",
+        "option name Weights type string default <autodiscover>",
+        "option name Network type string default <autodiscover>",
+    ]
+    .iter()
+    .filter_map(UciOptionDef::parse)
+    .collect();
+
+    let aliases = OptionAliases::new();
+
+    assert_eq!(aliases.resolve("SyzygyPath", &declared), Some("TbPath"));
+    assert_eq!(aliases.resolve("multipv", &declared), Some("MultiPv"));
+    assert_eq!(aliases.resolve("Elo", &declared), Some("UCI_Elo"));
+    assert_eq!(aliases.resolve("Weights", &declared), Some("Weights"));
+    assert_eq!(aliases.resolve("Hash", &declared), None);
+
+    // user aliases take precedence
+    let aliases = aliases.alias("Weights", "Network");
+
+    assert_eq!(aliases.resolve("Weights", &declared), Some("Network"));
+}
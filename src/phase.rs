@@ -0,0 +1,373 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// fen of the starting position
+pub const STARTPOS_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// default number of plies considered opening
+pub const OPENING_PLIES: usize = 20;
+
+/// default non pawn material of both sides ( in pawns ) at or below which the game is an endgame,
+/// the starting position has 62
+pub const ENDGAME_MATERIAL: usize = 26;
+
+/// phase of the game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Phase {
+    /// opening
+    Opening,
+    /// middlegame
+    Middlegame,
+    /// endgame
+    Endgame,
+}
+
+/// phase implementation
+impl Phase {
+    /// index of the phase, in game order
+    fn index(self) -> usize {
+        match self {
+            Phase::Opening => 0,
+            Phase::Middlegame => 1,
+            Phase::Endgame => 2,
+        }
+    }
+}
+
+/// value of a non pawn piece in pawns, 0 for pawns, kings and other chars
+fn piece_value(piece: char) -> usize {
+    match piece.to_ascii_lowercase() {
+        'n' | 'b' => 3,
+        'r' => 5,
+        'q' => 9,
+        _ => 0,
+    }
+}
+
+/// non pawn material of both sides in pawns ( knight and bishop 3, rook 5, queen 9 ),
+/// counted from the piece placement of the fen
+pub fn fen_material(fen: &str) -> usize {
+    fen.split_whitespace()
+        .next()
+        .unwrap_or("")
+        .chars()
+        .map(piece_value)
+        .sum()
+}
+
+/// rank and file index of a square given by its uci file and rank
+fn square(file: u8, rank: u8) -> Option<(usize, usize)> {
+    match (file, rank) {
+        (b'a'..=b'h', b'1'..=b'8') => Some(((rank - b'1') as usize, (file - b'a') as usize)),
+        _ => None,
+    }
+}
+
+/// non pawn material of both sides in pawns after the uci moves were played from the
+/// position of the fen, the moves are replayed on the piece placement for captures,
+/// castling and promotions without checking legality, None if the piece placement
+/// is malformed or a move is malformed or starts on an empty square
+pub fn fen_material_after(fen: &str, moves: &str) -> Option<usize> {
+    // board by rank and file, a1 first
+    let mut board = [[None::<char>; 8]; 8];
+
+    for (row, pieces) in fen.split_whitespace().next()?.split('/').enumerate() {
+        let rank = 7usize.checked_sub(row)?;
+
+        let mut file = 0;
+
+        for c in pieces.chars() {
+            match c.to_digit(10) {
+                Some(empty) => file += empty as usize,
+                _ => {
+                    *board[rank].get_mut(file)? = Some(c);
+
+                    file += 1;
+                }
+            }
+        }
+    }
+
+    let mut material = fen_material(fen);
+
+    for uci in moves.split_whitespace().filter(|uci| *uci != "0000") {
+        let bytes = uci.as_bytes();
+
+        if !(4..=5).contains(&bytes.len()) {
+            return None;
+        }
+
+        let (rank, from) = square(bytes[0], bytes[1])?;
+        let (to_rank, to) = square(bytes[2], bytes[3])?;
+
+        let piece = board[rank][from].take()?;
+
+        let white = piece.is_ascii_uppercase();
+
+        let target = board[to_rank][to];
+
+        let own_rook = target == Some(if white { 'R' } else { 'r' });
+
+        // castling, also sent as the king taking its own rook ( chess960 )
+        if piece.eq_ignore_ascii_case(&'k')
+            && (rank == to_rank)
+            && (own_rook || (from.max(to) - from.min(to) == 2))
+        {
+            let (rook_from, king_to, rook_to) = match (own_rook, to > from) {
+                (true, true) => (to, 6, 5),
+                (true, false) => (to, 2, 3),
+                (false, true) => (7, 6, 5),
+                (false, false) => (0, 2, 3),
+            };
+
+            let rook = board[rank][rook_from].take();
+
+            board[rank][king_to] = Some(piece);
+            board[rank][rook_to] = rook;
+
+            continue;
+        }
+
+        // en passant, a pawn moving diagonally to an empty square
+        if piece.eq_ignore_ascii_case(&'p') && (from != to) && target.is_none() {
+            board[rank][to] = None;
+        }
+
+        if let Some(captured) = target {
+            material = material.saturating_sub(piece_value(captured));
+        }
+
+        let piece = match bytes.get(4).map(|promotion| *promotion as char) {
+            Some(promotion @ ('q' | 'r' | 'b' | 'n')) => {
+                material += piece_value(promotion);
+
+                match white {
+                    true => promotion.to_ascii_uppercase(),
+                    _ => promotion,
+                }
+            }
+            Some(_) => return None,
+            _ => piece,
+        };
+
+        board[to_rank][to] = Some(piece);
+    }
+
+    Some(material)
+}
+
+/// plies played before the position of the fen, from its fullmove number and side to move
+pub fn fen_ply(fen: &str) -> usize {
+    let mut fields = fen.split_whitespace().skip(1);
+
+    let black = fields.next() == Some("b");
+
+    let fullmove = fields
+        .nth(3)
+        .and_then(|fullmove| fullmove.parse::<usize>().ok())
+        .unwrap_or(1)
+        .max(1);
+
+    (fullmove - 1) * 2 + (black as usize)
+}
+
+/// classifies positions into game phases by material and ply, and optionally assigns
+/// a search depth to each phase
+///
+/// a position is an endgame once the material drops to the endgame threshold,
+/// otherwise it is in the opening for the first opening plies and a middlegame after
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhaseClassifier {
+    /// number of plies considered opening
+    opening_plies: usize,
+    /// non pawn material at or below which the game is an endgame
+    endgame_material: usize,
+    /// search depth by phase index
+    depths: [Option<usize>; 3],
+}
+
+/// phase classifier default
+impl Default for PhaseClassifier {
+    fn default() -> Self {
+        Self {
+            opening_plies: OPENING_PLIES,
+            endgame_material: ENDGAME_MATERIAL,
+            depths: [None; 3],
+        }
+    }
+}
+
+/// phase classifier implementation
+impl PhaseClassifier {
+    /// create new phase classifier with default thresholds and no depths
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// set number of plies considered opening and return self
+    pub fn opening_plies(mut self, plies: usize) -> Self {
+        self.opening_plies = plies;
+
+        self
+    }
+
+    /// set endgame material threshold and return self
+    pub fn endgame_material(mut self, material: usize) -> Self {
+        self.endgame_material = material;
+
+        self
+    }
+
+    /// set search depth for phase and return self
+    pub fn depth(mut self, phase: Phase, depth: usize) -> Self {
+        self.depths[phase.index()] = Some(depth);
+
+        self
+    }
+
+    /// search depth for phase, None if not set
+    pub fn depth_for(&self, phase: Phase) -> Option<usize> {
+        self.depths[phase.index()]
+    }
+
+    /// phase of the position given by fen after further plies were played
+    ///
+    /// material is counted from the fen only, captures in the further plies are not seen,
+    /// see classify_moves
+    pub fn classify(&self, fen: &str, plies: usize) -> Phase {
+        self.classify_material(fen_material(fen), fen_ply(fen) + plies)
+    }
+
+    /// phase of the position given by fen after the uci moves were played, replaying
+    /// the moves for captures and promotions, None if a move can not be replayed
+    /// and the fen is not an endgame already
+    pub fn classify_moves(&self, fen: &str, moves: &str) -> Option<Phase> {
+        let plies = fen_ply(fen) + moves.split_whitespace().count();
+
+        match fen_material_after(fen, moves) {
+            Some(material) => Some(self.classify_material(material, plies)),
+            // material only goes down but for promotions, so an endgame stays one
+            _ if fen_material(fen) <= self.endgame_material => Some(Phase::Endgame),
+            _ => None,
+        }
+    }
+
+    /// phase by non pawn material and ply
+    fn classify_material(&self, material: usize, ply: usize) -> Phase {
+        if material <= self.endgame_material {
+            return Phase::Endgame;
+        }
+
+        if ply < self.opening_plies {
+            return Phase::Opening;
+        }
+
+        Phase::Middlegame
+    }
+}
+
+#[test]
+fn fen_counts() {
+    assert_eq!(fen_material(STARTPOS_FEN), 62);
+    assert_eq!(fen_material("8/5k2/8/8/8/2R5/5K2/8 w - - 0 60"), 5);
+
+    assert_eq!(fen_ply(STARTPOS_FEN), 0);
+    assert_eq!(
+        fen_ply("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2"),
+        2
+    );
+    assert_eq!(fen_ply("8/5k2/8/8/8/2R5/5K2/8 b - - 0 60"), 119);
+    assert_eq!(fen_ply("8/5k2/8/8/8/2R5/5K2/8 w"), 0);
+}
+
+#[test]
+fn material_after_moves() {
+    assert_eq!(fen_material_after(STARTPOS_FEN, ""), Some(62));
+
+    // scandinavian queen trade: 1. e4 d5 2. exd5 Qxd5 3. Qf3 Qxf3 4. Nxf3
+    let moves = "e2e4 d7d5 e4d5 d8d5 d1f3 d5f3 g1f3";
+
+    assert_eq!(fen_material_after(STARTPOS_FEN, moves), Some(44));
+
+    // castling moves the rook, so that it can be taken on its new square
+    let fen = "r3k3/8/8/8/8/8/8/4K2R w K - 0 1";
+
+    assert_eq!(fen_material_after(fen, "e1g1 a8f8"), Some(10));
+    assert_eq!(fen_material_after(fen, "e1h1 a8f8"), Some(10));
+    assert_eq!(fen_material_after(fen, "e1g1 a8f8 f1f8"), Some(5));
+
+    // promotion adds material, en passant takes a pawn
+    assert_eq!(
+        fen_material_after("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8q"),
+        Some(9)
+    );
+    assert_eq!(
+        fen_material_after("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6 e8d7 d6d7"),
+        Some(0)
+    );
+
+    assert_eq!(fen_material_after(STARTPOS_FEN, "e3e4"), None);
+    assert_eq!(fen_material_after(STARTPOS_FEN, "e2e4x"), None);
+    assert_eq!(fen_material_after(STARTPOS_FEN, "e9e4"), None);
+}
+
+#[test]
+fn classify_phases() {
+    let classifier = PhaseClassifier::new().depth(Phase::Endgame, 30);
+
+    assert_eq!(classifier.classify(STARTPOS_FEN, 4), Phase::Opening);
+    assert_eq!(classifier.classify(STARTPOS_FEN, 20), Phase::Middlegame);
+    assert_eq!(
+        classifier.classify("8/5k2/8/8/8/2R5/5K2/8 w - - 0 60", 0),
+        Phase::Endgame
+    );
+
+    // queens traded early
+    let fen = "rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNB1KBNR w - - 0 8";
+
+    assert_eq!(classifier.classify(fen, 0), Phase::Opening);
+    assert_eq!(
+        classifier.clone().endgame_material(44).classify(fen, 0),
+        Phase::Endgame
+    );
+    assert_eq!(
+        classifier.clone().opening_plies(10).classify(fen, 0),
+        Phase::Middlegame
+    );
+
+    assert_eq!(classifier.depth_for(Phase::Endgame), Some(30));
+    assert_eq!(classifier.depth_for(Phase::Opening), None);
+
+    let moves = "e2e4 d7d5 e4d5 d8d5 d1f3 d5f3 g1f3";
+
+    assert_eq!(
+        classifier.classify_moves(STARTPOS_FEN, moves),
+        Some(Phase::Opening)
+    );
+    assert_eq!(
+        classifier
+            .clone()
+            .endgame_material(44)
+            .classify_moves(STARTPOS_FEN, moves),
+        Some(Phase::Endgame)
+    );
+    assert_eq!(classifier.classify_moves(STARTPOS_FEN, "e3e4"), None);
+    assert_eq!(
+        classifier.classify_moves("8/5k2/8/8/8/2R5/5K2/8 w - - 0 60", "e3e4"),
+        Some(Phase::Endgame)
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn phase_serde() {
+    assert_eq!(
+        serde_json::to_string(&Phase::Endgame).unwrap(),
+        "\"Endgame\""
+    );
+    assert_eq!(
+        serde_json::from_str::<Phase>("\"Opening\"").unwrap(),
+        Phase::Opening
+    );
+}
@@ -0,0 +1,11 @@
+//! alloc items that std would otherwise bring into scope,
+//! used by modules that also build without std
+
+#[allow(unused_imports)]
+pub(crate) use alloc::{
+    borrow::ToOwned,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
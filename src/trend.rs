@@ -0,0 +1,199 @@
+use crate::analysis::*;
+use crate::prelude::*;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// default number of depths the trend is computed over
+pub const TREND_WINDOW: usize = 4;
+
+/// score changes up to this many centipawns are considered noise
+pub const STABLE_CP: i64 = 20;
+
+/// drop in centipawns over the window considered a collapse
+pub const COLLAPSE_CP: i64 = 100;
+
+/// mate scores are counted as this many centipawns
+const MATE_CP: i64 = 10000;
+
+/// direction of the score over the recent depths, from the point of view of the side to move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Trend {
+    /// score changes stay within noise
+    Stable,
+    /// score is growing
+    Improving,
+    /// score is shrinking
+    Declining,
+    /// score dropped sharply
+    Collapsing,
+    /// score swings up and down between depths
+    Oscillating,
+}
+
+/// tracks the score of the best line over the last depths of a search and derives its trend
+#[derive(Debug, Clone)]
+pub struct TrendTracker {
+    /// number of depths the trend is computed over
+    window: usize,
+    /// depth and centipawn score of the best line, oldest first
+    scores: Vec<(usize, i64)>,
+    /// last trend computed
+    trend: Option<Trend>,
+}
+
+/// trend tracker implementation
+impl TrendTracker {
+    /// create new trend tracker computing the trend over the last window depths
+    /// ( at least two )
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(2),
+            scores: vec![],
+            trend: None,
+        }
+    }
+
+    /// current trend, None until two depths were completed
+    pub fn trend(&self) -> Option<Trend> {
+        self.trend
+    }
+
+    /// forget the scores, e.g. when a new search starts
+    pub fn reset(&mut self) {
+        self.scores.clear();
+        self.trend = None;
+    }
+
+    /// push analysis info, returns the new trend if it changed
+    ///
+    /// only exact scores of the best line count, a shallower depth than the last one
+    /// starts over as it belongs to a new search
+    pub fn push(&mut self, ai: &AnalysisInfo) -> Option<Trend> {
+        if (ai.multipv > 1) || !ai.is_exact() || ai.pv().is_none() {
+            return None;
+        }
+
+        let score = ai.score.score_value().clamp(-MATE_CP, MATE_CP);
+
+        match self.scores.last_mut() {
+            Some((depth, _)) if ai.depth < *depth => self.reset(),
+            Some((depth, last_score)) if ai.depth == *depth => *last_score = score,
+            _ => (),
+        }
+
+        if self.scores.last().map(|(depth, _)| *depth) != Some(ai.depth) {
+            self.scores.push((ai.depth, score));
+        }
+
+        if self.scores.len() > self.window {
+            self.scores.remove(0);
+        }
+
+        let trend = self.compute();
+
+        if trend == self.trend {
+            return None;
+        }
+
+        self.trend = trend;
+
+        trend
+    }
+
+    /// trend of the stored scores
+    fn compute(&self) -> Option<Trend> {
+        let (first, last) = match (self.scores.first(), self.scores.last()) {
+            (Some((_, first)), Some((_, last))) if self.scores.len() >= 2 => (*first, *last),
+            _ => return None,
+        };
+
+        let total = last - first;
+
+        let swings: Vec<bool> = self
+            .scores
+            .windows(2)
+            .map(|pair| pair[1].1 - pair[0].1)
+            .filter(|delta| delta.abs() > STABLE_CP)
+            .map(|delta| delta > 0)
+            .collect();
+
+        let reversals = swings.windows(2).filter(|pair| pair[0] != pair[1]).count();
+
+        let trend = match total {
+            _ if total <= -COLLAPSE_CP => Trend::Collapsing,
+            _ if reversals >= 2 => Trend::Oscillating,
+            _ if total > STABLE_CP => Trend::Improving,
+            _ if total < -STABLE_CP => Trend::Declining,
+            _ => Trend::Stable,
+        };
+
+        Some(trend)
+    }
+}
+
+#[cfg(test)]
+fn push_lines(tracker: &mut TrendTracker, lines: &[&str]) -> Vec<Option<Trend>> {
+    lines
+        .iter()
+        .map(|line| {
+            let mut ai = AnalysisInfo::new();
+
+            ai.parse(line).unwrap();
+
+            tracker.push(&ai)
+        })
+        .collect()
+}
+
+#[test]
+fn trend_changes() {
+    let mut tracker = TrendTracker::new(4);
+
+    let trends = push_lines(
+        &mut tracker,
+        &[
+            "info depth 1 score cp 20 pv e2e4",
+            "info depth 2 score cp 25 pv e2e4",
+            "info depth 3 score cp 60 pv e2e4",
+            "info depth 3 score cp 70 lowerbound pv e2e4",
+            "info depth 4 score cp 80 pv e2e4",
+            "info depth 5 score cp -90 pv d2d4",
+        ],
+    );
+
+    assert_eq!(
+        trends,
+        vec![
+            None,
+            Some(Trend::Stable),
+            Some(Trend::Improving),
+            None,
+            None,
+            Some(Trend::Collapsing)
+        ]
+    );
+}
+
+#[test]
+fn trend_oscillating() {
+    let mut tracker = TrendTracker::new(5);
+
+    push_lines(
+        &mut tracker,
+        &[
+            "info depth 6 score cp 30 pv e2e4",
+            "info depth 7 score cp 80 pv e2e4",
+            "info depth 8 score cp 20 pv d2d4",
+            "info depth 9 score cp 70 pv e2e4",
+        ],
+    );
+
+    assert_eq!(tracker.trend(), Some(Trend::Oscillating));
+
+    // a new search starts over
+    push_lines(&mut tracker, &["info depth 1 score mate 3 pv h5f7"]);
+
+    assert_eq!(tracker.trend(), None);
+}
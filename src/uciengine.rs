@@ -1,18 +1,33 @@
-use log::{debug, error, info, log_enabled, Level};
+use log::{debug, error, info, log_enabled, warn, Level};
 
 use envor::envor::env_true;
 
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::process::Stdio;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
 use tokio::process::Command;
 use tokio::sync::*;
+use tokio::time::Instant;
+
+use thiserror::Error;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 use crate::analysis::*;
+use crate::event::*;
+use crate::history::*;
+use crate::multipv::*;
+use crate::options::*;
+use crate::phase::*;
+use crate::trend::*;
 
 /// enum of possible position specifiers
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PosSpec {
     /// starting position
     Startpos,
@@ -26,6 +41,7 @@ use PosSpec::*;
 
 /// go command job
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct GoJob {
     /// uci options as key value pairs
     uci_options: HashMap<String, String>,
@@ -45,12 +61,24 @@ pub struct GoJob {
     ponderhit: bool,
     /// pondermiss ( alias to awaited stop )
     pondermiss: bool,
+    /// move overhead in milliseconds
+    move_overhead: Option<usize>,
+    /// isready ( awaited readyok )
+    isready: bool,
+    /// uci handshake ( awaited uciok )
+    #[cfg_attr(feature = "serde", serde(skip))]
+    handshake: Option<HandshakeConfig>,
+    /// handshake result sender
+    #[cfg_attr(feature = "serde", serde(skip))]
+    htx: Option<oneshot::Sender<Result<Handshake, HandshakeError>>>,
     /// result sender
+    #[cfg_attr(feature = "serde", serde(skip))]
     rtx: Option<oneshot::Sender<GoResult>>,
 }
 
 /// time control ( all values are in milliseconds )
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Timecontrol {
     /// white time
     pub wtime: usize,
@@ -62,11 +90,54 @@ pub struct Timecontrol {
     pub binc: usize,
 }
 
-/// implementation of time control
+/// time control implementation
 impl Timecontrol {
+    /// create time control from durations, rounded down to milliseconds
+    pub fn from_durations(
+        wtime: Duration,
+        winc: Duration,
+        btime: Duration,
+        binc: Duration,
+    ) -> Self {
+        Self {
+            wtime: duration_ms(wtime),
+            winc: duration_ms(winc),
+            btime: duration_ms(btime),
+            binc: duration_ms(binc),
+        }
+    }
+
+    /// white time as duration
+    pub fn wtime_duration(&self) -> Duration {
+        Duration::from_millis(self.wtime as u64)
+    }
+
+    /// white increment as duration
+    pub fn winc_duration(&self) -> Duration {
+        Duration::from_millis(self.winc as u64)
+    }
+
+    /// black time as duration
+    pub fn btime_duration(&self) -> Duration {
+        Duration::from_millis(self.btime as u64)
+    }
+
+    /// black increment as duration
+    pub fn binc_duration(&self) -> Duration {
+        Duration::from_millis(self.binc as u64)
+    }
+}
+
+/// duration in whole milliseconds, saturating
+fn duration_ms(duration: Duration) -> usize {
+    duration.as_millis().min(usize::MAX as u128) as usize
+}
+
+/// default time control
+impl Default for Timecontrol {
     /// create default time control
     /// ( one minute thinking time for both sides, no increment )
-    pub fn default() -> Self {
+    fn default() -> Self {
         Self {
             wtime: 60000,
             winc: 0,
@@ -76,6 +147,314 @@ impl Timecontrol {
     }
 }
 
+/// default go command job
+impl Default for GoJob {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// engine process configuration
+///
+/// arguments, environment values, the working directory and init lines may contain
+/// {name} placeholders, replaced by template variables set with var when the engine
+/// is spawned, {engine_dir} is the directory of the engine executable, looked up in PATH
+/// for bare executable names and the current directory if not found there
+#[derive(Debug, Clone)]
+pub struct EngineConfig {
+    /// engine executable path
+    path: String,
+    /// command line arguments
+    args: Vec<String>,
+    /// environment variables set for the engine process
+    envs: Vec<(String, String)>,
+    /// working directory of the engine process
+    current_dir: Option<String>,
+    /// force the C locale
+    c_locale: bool,
+    /// lines written to the engine input after spawning, before any job
+    init: Vec<String>,
+    /// template variables
+    vars: Vec<(String, String)>,
+}
+
+/// engine process configuration implementation
+impl EngineConfig {
+    /// create new engine configuration for the executable at path,
+    /// the engine inherits environment and working directory by default
+    pub fn new<T>(path: T) -> Self
+    where
+        T: core::fmt::Display,
+    {
+        Self {
+            path: format!("{}", path),
+            args: vec![],
+            envs: vec![],
+            current_dir: None,
+            c_locale: false,
+            init: vec![],
+            vars: vec![],
+        }
+    }
+
+    /// add command line argument and return self
+    pub fn arg<T>(mut self, arg: T) -> Self
+    where
+        T: ToString,
+    {
+        self.args.push(arg.to_string());
+
+        self
+    }
+
+    /// add command line arguments and return self
+    pub fn args(mut self, args: &[impl ToString]) -> Self {
+        self.args.extend(args.iter().map(|arg| arg.to_string()));
+
+        self
+    }
+
+    /// set environment variable and return self
+    pub fn env<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: core::fmt::Display,
+        V: core::fmt::Display,
+    {
+        self.envs.push((format!("{}", key), format!("{}", value)));
+
+        self
+    }
+
+    /// set working directory and return self
+    pub fn current_dir<T>(mut self, dir: T) -> Self
+    where
+        T: core::fmt::Display,
+    {
+        self.current_dir = Some(format!("{}", dir));
+
+        self
+    }
+
+    /// force LC_ALL=C for the engine process and return self,
+    /// for engines printing locale formatted numbers
+    pub fn c_locale(mut self) -> Self {
+        self.c_locale = true;
+
+        self
+    }
+
+    /// add line written to the engine input after spawning and return self,
+    /// init lines are sent before any job, in particular before the handshake,
+    /// for engines expecting commands outside the protocol ( e.g. uci for
+    /// engines speaking several protocols )
+    pub fn init<T>(mut self, line: T) -> Self
+    where
+        T: core::fmt::Display,
+    {
+        self.init.push(format!("{}", line));
+
+        self
+    }
+
+    /// set template variable and return self
+    pub fn var<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: core::fmt::Display,
+        V: core::fmt::Display,
+    {
+        self.vars.push((format!("{}", key), format!("{}", value)));
+
+        self
+    }
+
+    /// directory of the engine executable
+    fn engine_dir(&self) -> String {
+        let path = std::path::Path::new(&self.path);
+
+        match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_string_lossy().to_string(),
+            // a bare name is resolved by the os through PATH when spawning
+            _ => std::env::var_os("PATH")
+                .and_then(|paths| {
+                    std::env::split_paths(&paths).find(|dir| dir.join(path).is_file())
+                })
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .map(|dir| dir.to_string_lossy().to_string())
+                .unwrap_or_else(|| ".".to_string()),
+        }
+    }
+
+    /// replace placeholders in value by template variables
+    fn expand(&self, value: &str) -> String {
+        let engine_dir = self.engine_dir();
+
+        let builtin = ("engine_dir".to_string(), engine_dir);
+
+        self.vars
+            .iter()
+            .chain(core::iter::once(&builtin))
+            .fold(value.to_string(), |value, (key, var)| {
+                value.replace(&format!("{{{}}}", key), var)
+            })
+    }
+}
+
+/// uci handshake configuration
+#[derive(Debug, Clone)]
+pub struct HandshakeConfig {
+    /// maximum number of preamble lines skipped before uciok
+    banner_limit: usize,
+    /// maximum time to wait for uciok
+    timeout: Duration,
+    /// fail if uciok does not arrive in time
+    strict: bool,
+    /// registration name and code sent if the engine requires registration
+    register: Option<(String, String)>,
+}
+
+/// default uci handshake configuration
+impl Default for HandshakeConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// uci handshake configuration implementation
+impl HandshakeConfig {
+    /// create new handshake configuration
+    /// ( at most 100 preamble lines, ten seconds to answer uciok, strict )
+    pub fn new() -> Self {
+        Self {
+            banner_limit: 100,
+            timeout: Duration::from_secs(10),
+            strict: true,
+            register: None,
+        }
+    }
+
+    /// set maximum number of preamble lines and return self
+    pub fn banner_limit(mut self, banner_limit: usize) -> Self {
+        self.banner_limit = banner_limit;
+
+        self
+    }
+
+    /// set maximum time to wait for uciok and return self
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+
+        self
+    }
+
+    /// set strictness and return self,
+    /// if not strict, a missing uciok does not fail the handshake,
+    /// instead engine defaults are assumed, for minimal engines
+    /// that skip parts of the handshake
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+
+        self
+    }
+
+    /// set registration name and code and return self,
+    /// they are sent if the engine reports registration error after uciok,
+    /// otherwise register later is sent so that the engine does not wait for them
+    pub fn register<N, C>(mut self, name: N, code: C) -> Self
+    where
+        N: core::fmt::Display,
+        C: core::fmt::Display,
+    {
+        self.register = Some((format!("{}", name), format!("{}", code)));
+
+        self
+    }
+}
+
+/// status of the copy protection or registration check reported by the engine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    /// check in progress
+    Checking,
+    /// check passed
+    Ok,
+    /// check failed
+    Error,
+}
+
+/// check status implementation
+impl CheckStatus {
+    /// parse status token ( checking, ok or error )
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "checking" => Some(CheckStatus::Checking),
+            "ok" => Some(CheckStatus::Ok),
+            "error" => Some(CheckStatus::Error),
+            _ => None,
+        }
+    }
+}
+
+/// engine identification sent on uci ( id name, id author )
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EngineId {
+    /// engine name, usually including the version
+    pub name: Option<String>,
+    /// engine author
+    pub author: Option<String>,
+}
+
+/// engine identification implementation
+impl EngineId {
+    /// best effort version from the engine name, the first word starting with a digit,
+    /// optionally prefixed with v ( Stockfish 16.1 -> 16.1, Lc0 v0.30.0 -> 0.30.0 )
+    pub fn version(&self) -> Option<String> {
+        self.name.as_ref()?.split_whitespace().find_map(|word| {
+            let version = word
+                .strip_prefix(['v', 'V'])
+                .filter(|version| !version.is_empty())
+                .unwrap_or(word);
+
+            match version.chars().next() {
+                Some(c) if c.is_ascii_digit() => Some(version.to_string()),
+                _ => None,
+            }
+        })
+    }
+}
+
+/// outcome of the uci handshake
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Handshake {
+    /// engine identification
+    pub id: EngineId,
+    /// preamble lines that are not part of the uci protocol, e.g. startup banners
+    pub banner: Vec<String>,
+    /// id, option and other protocol lines received before uciok
+    pub lines: Vec<String>,
+    /// options declared by the engine
+    pub options: Vec<UciOptionDef>,
+    /// copy protection status reported after uciok, None if the engine is not protected
+    pub copyprotection: Option<CheckStatus>,
+    /// registration status reported after uciok, None if the engine needs no registration
+    pub registration: Option<CheckStatus>,
+    /// uciok did not arrive in time and engine defaults are assumed
+    pub degraded: bool,
+}
+
+/// HandshakeError captures failures of the uci handshake
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum HandshakeError {
+    #[error("engine did not answer uciok within {0:?}")]
+    Timeout(Duration),
+    #[error("engine sent more than {0} preamble lines")]
+    BannerLimit(usize),
+    #[error("engine output ended during the handshake")]
+    Closed,
+}
+
+/// first tokens of engine lines that belong to the uci handshake
+const HANDSHAKE_KEYWORDS: [&str; 4] = ["id", "option", "copyprotection", "registration"];
+
 /// go command job implementation
 impl GoJob {
     /// create new GoJob with defaults
@@ -91,6 +470,10 @@ impl GoJob {
             ponder: false,
             ponderhit: false,
             pondermiss: false,
+            move_overhead: None,
+            isready: false,
+            handshake: None,
+            htx: None,
         }
     }
 
@@ -107,24 +490,46 @@ impl GoJob {
         self
     }
 
+    /// option names and values set by the commands of the job
+    fn set_options(&self) -> Vec<(String, String)> {
+        self.to_commands()
+            .iter()
+            .filter_map(|command| command.strip_prefix("setoption name "))
+            .filter_map(|command| command.split_once(" value "))
+            .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+            .collect()
+    }
+
     /// convert go job to commands
     pub fn to_commands(&self) -> Vec<String> {
         let mut commands: Vec<String> = vec![];
 
         if self.ponderhit {
-            commands.push(format!("{}", "ponderhit"));
+            commands.push("ponderhit".to_string());
 
             return commands;
         }
 
         if self.pondermiss {
-            commands.push(format!("{}", "stop"));
+            commands.push("stop".to_string());
+
+            return commands;
+        }
+
+        if self.isready {
+            commands.push("isready".to_string());
+
+            return commands;
+        }
+
+        if self.handshake.is_some() {
+            commands.push("uci".to_string());
 
             return commands;
         }
 
         if let Some(command) = &self.custom_command {
-            commands.push(format!("{}", command));
+            commands.push(command.to_string());
 
             return commands;
         }
@@ -157,7 +562,25 @@ impl GoJob {
 
         let mut go_command = "go".to_string();
 
+        // the clock of the opponent is passed on as it is
+        let own_clock = match self.side_to_move() {
+            Some(Color::White) => Some("wtime"),
+            Some(Color::Black) => Some("btime"),
+            _ => None,
+        };
+
         for (key, value) in &self.go_options {
+            let mut value = value.to_string();
+
+            let is_budget = (key == "movetime") || (Some(key.as_str()) == own_clock);
+
+            // the overhead is taken from the time budgets, so that the move arrives in time
+            if let (Some(move_overhead), true) = (self.move_overhead, is_budget) {
+                if let Ok(budget) = value.parse::<usize>() {
+                    value = format!("{}", budget.saturating_sub(move_overhead).max(1));
+                }
+            }
+
             go_command = go_command + &format!(" {} {}", key, value);
         }
 
@@ -170,6 +593,88 @@ impl GoJob {
         commands
     }
 
+    /// job setting up the same position without searching,
+    /// None if the job does not specify a position
+    fn position(&self) -> Option<Self> {
+        let pos_spec = match self.pos_spec {
+            Startpos => Startpos,
+            Fen => Fen,
+            No => return None,
+        };
+
+        let mut go_job = GoJob::new();
+
+        go_job.pos_spec = pos_spec;
+        go_job.pos_fen = self.pos_fen.clone();
+        go_job.pos_moves = self.pos_moves.clone();
+
+        Some(go_job)
+    }
+
+    /// add move to position moves and return self
+    fn push_move(mut self, uci: &str) -> Self {
+        self.pos_moves = match self.pos_moves {
+            Some(moves) if !moves.trim().is_empty() => Some(format!("{} {}", moves.trim(), uci)),
+            _ => Some(uci.to_string()),
+        };
+
+        self
+    }
+
+    /// side to move in the position of the job, counting the position moves,
+    /// None if the job specifies no position
+    pub fn side_to_move(&self) -> Option<Color> {
+        let side = match self.pos_spec {
+            Startpos => Color::White,
+            Fen => match self.pos_fen.as_deref()?.split_whitespace().nth(1) {
+                Some("b") => Color::Black,
+                _ => Color::White,
+            },
+            No => return None,
+        };
+
+        let plies = self
+            .pos_moves
+            .as_deref()
+            .map_or(0, |moves| moves.split_whitespace().count());
+
+        match plies % 2 {
+            0 => Some(side),
+            _ => Some(side.opposite()),
+        }
+    }
+
+    /// game phase of the position of the job after the position moves,
+    /// None if the job specifies no position or its moves can not be replayed
+    /// ( see PhaseClassifier::classify_moves )
+    pub fn phase(&self, classifier: &PhaseClassifier) -> Option<Phase> {
+        let fen = match self.pos_spec {
+            Startpos => STARTPOS_FEN,
+            Fen => self.pos_fen.as_deref()?,
+            No => return None,
+        };
+
+        classifier.classify_moves(fen, self.pos_moves.as_deref().unwrap_or(""))
+    }
+
+    /// set depth go option to the depth the classifier assigns to the phase of the position
+    /// and return self, a depth already set is kept
+    pub fn phase_depth(mut self, classifier: &PhaseClassifier) -> Self {
+        if self.go_options.contains_key("depth") {
+            return self;
+        }
+
+        if let Some(depth) = self
+            .phase(classifier)
+            .and_then(|phase| classifier.depth_for(phase))
+        {
+            self.go_options
+                .insert("depth".to_string(), format!("{}", depth));
+        }
+
+        self
+    }
+
     /// set ponder and return self
     pub fn set_ponder(mut self, value: bool) -> Self {
         self.ponder = value;
@@ -198,6 +703,23 @@ impl GoJob {
         self
     }
 
+    /// set isready and return self,
+    /// the job result arrives when the engine answers readyok
+    pub fn isready(mut self) -> Self {
+        self.isready = true;
+
+        self
+    }
+
+    /// whether the job issues a go command
+    fn issues_go(&self) -> bool {
+        !(self.ponderhit
+            || self.pondermiss
+            || self.isready
+            || self.handshake.is_some()
+            || self.custom_command.is_some())
+    }
+
     /// set position fen and return self
     pub fn pos_fen<T>(mut self, fen: T) -> Self
     where
@@ -261,6 +783,49 @@ impl GoJob {
         self
     }
 
+    /// set move overhead in milliseconds and return self, to compensate for the delay
+    /// of slow transports, it is passed as the Move Overhead uci option if the engine
+    /// declares it ( see UciEngine::go ), otherwise it is subtracted from movetime
+    /// and the clock of the side to move
+    pub fn move_overhead(mut self, ms: usize) -> Self {
+        self.move_overhead = Some(ms);
+
+        self
+    }
+
+    /// add the Move Overhead uci option under the name the engine declares it as,
+    /// unless the job sets it already or has no move overhead, the engine then
+    /// compensates for the overhead and the time budgets are left alone
+    fn move_overhead_option(mut self, declared: &[UciOptionDef]) -> Self {
+        let move_overhead = match self.move_overhead {
+            Some(move_overhead) => move_overhead,
+            _ => return self,
+        };
+
+        let name = match OptionAliases::new().resolve("Move Overhead", declared) {
+            Some(name) => name,
+            _ => return self,
+        };
+
+        self.move_overhead = None;
+
+        if self.uci_options.contains_key(name) {
+            return self;
+        }
+
+        self.uci_opt(name, move_overhead)
+    }
+
+    /// set move overhead as duration and return self, see move_overhead
+    pub fn move_overhead_duration(self, overhead: Duration) -> Self {
+        self.move_overhead(duration_ms(overhead))
+    }
+
+    /// set movetime go option as duration and return self
+    pub fn movetime(self, movetime: Duration) -> Self {
+        self.go_opt("movetime", duration_ms(movetime))
+    }
+
     /// set time control and return self
     pub fn tc(mut self, tc: Timecontrol) -> Self {
         self.go_options
@@ -276,8 +841,115 @@ impl GoJob {
     }
 }
 
+/// named analysis settings, search limits and option changes that are applied
+/// for a search and reverted afterwards ( e.g. quick, deep, tb-assisted )
+#[derive(Debug, Clone, Default)]
+pub struct AnalysisProfile {
+    /// go command options as key value pairs
+    go_options: Vec<(String, String)>,
+    /// uci options as key value pairs
+    uci_options: Vec<(String, String)>,
+}
+
+/// analysis profile implementation
+impl AnalysisProfile {
+    /// create new empty analysis profile
+    pub fn new() -> Self {
+        Self {
+            go_options: vec![],
+            uci_options: vec![],
+        }
+    }
+
+    /// set go option as key value pair and return self
+    pub fn go_opt<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: core::fmt::Display,
+        V: core::fmt::Display,
+    {
+        self.go_options
+            .push((format!("{}", key), format!("{}", value)));
+
+        self
+    }
+
+    /// set uci option as key value pair and return self
+    pub fn uci_opt<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: core::fmt::Display,
+        V: core::fmt::Display,
+    {
+        self.uci_options
+            .push((format!("{}", key), format!("{}", value)));
+
+        self
+    }
+
+    /// add profile settings to go job, settings of the go job take precedence
+    fn apply(&self, mut go_job: GoJob) -> GoJob {
+        for (key, value) in &self.go_options {
+            go_job
+                .go_options
+                .entry(key.to_string())
+                .or_insert_with(|| value.to_string());
+        }
+
+        for (key, value) in &self.uci_options {
+            go_job
+                .uci_options
+                .entry(key.to_string())
+                .or_insert_with(|| value.to_string());
+        }
+
+        go_job
+    }
+
+    /// setoption commands reverting the uci options of the profile to the values sent
+    /// before it by lower case name, or the declared defaults for options not sent before,
+    /// options whose value the profile did not change are skipped, options without
+    /// value to revert to can not be reverted and are skipped
+    fn revert_commands(
+        &self,
+        declared: &[UciOptionDef],
+        sent: &HashMap<String, String>,
+        applied: &HashMap<String, String>,
+    ) -> Vec<String> {
+        let mut commands = vec![];
+
+        for (key, _) in &self.uci_options {
+            let value = match sent.get(&key.to_lowercase()) {
+                Some(value) => Some(value.to_string()),
+                _ => declared
+                    .iter()
+                    .find(|def| def.name.eq_ignore_ascii_case(key))
+                    .and_then(|def| def.kind.default_value())
+                    .map(|default| match default.as_str() {
+                        "" => "<empty>".to_string(),
+                        _ => default,
+                    }),
+            };
+
+            match value {
+                Some(value) if applied.get(key) == Some(&value) => (),
+                Some(value) => commands.push(format!("setoption name {} value {}", key, value)),
+                _ => {
+                    if log_enabled!(Level::Warn) {
+                        warn!(
+                            "no sent value or declared default to revert option {} to",
+                            key
+                        );
+                    }
+                }
+            }
+        }
+
+        commands
+    }
+}
+
 /// go command result
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct GoResult {
     /// best move if any
     pub bestmove: Option<String>,
@@ -285,17 +957,321 @@ pub struct GoResult {
     pub ponder: Option<String>,
     /// analysis info
     pub ai: AnalysisInfo,
+    /// best line per depth, empty unless history is retained ( see set_retain_history )
+    pub history: AnalysisHistory,
+    /// physically implausible values the engine sent during the search
+    /// ( at most MAX_SUSPECTS, see AnalysisInfo::parse_checked )
+    pub suspects: Vec<SuspectField>,
+    /// whether a verification search confirmed the score ( see go_verified )
+    pub verified: bool,
 }
 
-/// uci engine
-pub struct UciEngine {
-    gtx: mpsc::UnboundedSender<GoJob>,
-    pub ai: std::sync::Arc<std::sync::Mutex<AnalysisInfo>>,
-    pub atx: std::sync::Arc<broadcast::Sender<AnalysisInfo>>,
+/// go command result implementation
+impl GoResult {
+    /// structured best move, with (none) and 0000 mapped to no move,
+    /// None for jobs that do not end with bestmove ( e.g. isready )
+    pub fn best_move(&self) -> Option<BestMove> {
+        let bestmove = self.bestmove.as_ref()?;
+
+        match &self.ponder {
+            Some(ponder) => BestMove::parse(format!("{} ponder {}", bestmove, ponder)),
+            _ => BestMove::parse(bestmove),
+        }
+    }
 }
 
-/// uci engine implementation
-impl UciEngine {
+/// evaluation of a move of an explored line ( see UciEngine::explore )
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LineStep {
+    /// move of the line
+    pub uci: String,
+    /// best move of the engine in the position before the move, None if it found none
+    pub bestmove: Option<String>,
+    /// score of the best move, from the point of view of the side making the move
+    pub best_score: Score,
+    /// score after the move, from the point of view of the side making the move
+    pub score: Score,
+    /// centipawns the move loses against the best move, 0 if it is the best move
+    pub loss_cp: i64,
+}
+
+/// explored line, every move evaluated against best play ( see UciEngine::explore )
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LineExploration {
+    /// evaluation per move of the line, in order
+    pub steps: Vec<LineStep>,
+    /// index of the first move losing more than the margin, None if the line holds
+    pub wrong_at: Option<usize>,
+}
+
+/// maximum number of suspect values kept per search
+pub const MAX_SUSPECTS: usize = 32;
+
+/// number of recent lines kept in the transcript
+const TRANSCRIPT_LENGTH: usize = 32;
+
+/// recent commands sent to ( "> " ) and lines received from ( "< " ) the engine
+struct Transcript {
+    /// recent lines, oldest first
+    lines: std::collections::VecDeque<String>,
+}
+
+/// transcript implementation
+impl Transcript {
+    /// create new empty transcript
+    fn new() -> Self {
+        Self {
+            lines: std::collections::VecDeque::with_capacity(TRANSCRIPT_LENGTH),
+        }
+    }
+
+    /// add line, dropping the oldest one if the transcript is full
+    fn push(&mut self, line: String) {
+        if self.lines.len() == TRANSCRIPT_LENGTH {
+            self.lines.pop_front();
+        }
+
+        self.lines.push_back(line);
+    }
+
+    /// copy of the recent lines, oldest first
+    fn lines(&self) -> Vec<String> {
+        self.lines.iter().cloned().collect()
+    }
+}
+
+/// capacity of the event broadcast, enough for the default banner limit
+const EVENT_CAPACITY: usize = 128;
+
+/// maximum number of protocol errors kept until taken, further errors are only logged
+pub const MAX_PROTOCOL_ERRORS: usize = 16;
+
+/// time to wait for readyok before issuing a job after a search,
+/// so that an engine not answering isready does not stall the jobs
+const BARRIER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// ProtocolError captures violations of the command / response pairing
+#[derive(Error, Debug, Clone)]
+pub enum ProtocolError {
+    #[error("unsolicited engine response '{line}' before job {job}")]
+    Unsolicited {
+        /// response line ( bestmove or readyok )
+        line: String,
+        /// sequence number of the job about to be issued
+        job: u64,
+        /// recent engine communication
+        transcript: Vec<String>,
+    },
+}
+
+/// protocol error implementation
+impl ProtocolError {
+    /// recent engine communication at the time of the error
+    pub fn transcript(&self) -> &[String] {
+        match self {
+            ProtocolError::Unsolicited { transcript, .. } => transcript,
+        }
+    }
+}
+
+/// analysis info from the point of view of white if normalization applies to the search
+fn pov_ai(ai: AnalysisInfo, pov: &std::sync::Mutex<Option<Color>>) -> AnalysisInfo {
+    match *pov.lock().unwrap() {
+        Some(side) => ai.to_white_pov(side),
+        _ => ai,
+    }
+}
+
+/// analysis history normalized like pov_ai
+fn pov_history(
+    history: &AnalysisHistory,
+    pov: &std::sync::Mutex<Option<Color>>,
+) -> AnalysisHistory {
+    match *pov.lock().unwrap() {
+        Some(side) => history.to_white_pov(side),
+        _ => history.clone(),
+    }
+}
+
+/// write command to engine input, recording it in the transcript
+async fn write_command<W>(stdin: &mut W, transcript: &std::sync::Mutex<Transcript>, command: &str)
+where
+    W: AsyncWrite + Unpin,
+{
+    transcript.lock().unwrap().push(format!("> {}", command));
+
+    let command = format!("{}\n", command);
+
+    if log_enabled!(Level::Debug) {
+        debug!("issuing engine command : {}", command);
+    }
+
+    let write_result = stdin.write_all(command.as_bytes()).await;
+
+    if log_enabled!(Level::Debug) {
+        debug!("write result {:?}", write_result);
+    }
+}
+
+/// read engine lines up to uciok, skipping preamble lines within the configured limits,
+/// they are broadcast as banner events
+async fn read_handshake(
+    line_rx: &mut mpsc::UnboundedReceiver<String>,
+    config: &HandshakeConfig,
+    etx: &broadcast::Sender<UciEvent>,
+) -> Result<Handshake, HandshakeError> {
+    let deadline = Instant::now() + config.timeout;
+
+    let mut handshake = Handshake::default();
+
+    loop {
+        let line = match tokio::time::timeout_at(deadline, line_rx.recv()).await {
+            Ok(Some(line)) => line,
+            Ok(None) => return Err(HandshakeError::Closed),
+            _ if config.strict => return Err(HandshakeError::Timeout(config.timeout)),
+            _ => {
+                if log_enabled!(Level::Warn) {
+                    warn!(
+                        "engine did not answer uciok within {:?}, assuming defaults",
+                        config.timeout
+                    );
+                }
+
+                handshake.degraded = true;
+
+                return Ok(handshake);
+            }
+        };
+
+        match line.split_whitespace().next() {
+            Some("uciok") => return Ok(handshake),
+            Some(token) if HANDSHAKE_KEYWORDS.contains(&token) => {
+                match parse_line(&line) {
+                    Ok(UciEvent::Id { key, value }) if key == "name" => {
+                        handshake.id.name = Some(value)
+                    }
+                    Ok(UciEvent::Id { key, value }) if key == "author" => {
+                        handshake.id.author = Some(value)
+                    }
+                    Ok(UciEvent::Option(option)) => handshake.options.push(option),
+                    _ if token == "option" => {
+                        if log_enabled!(Level::Warn) {
+                            warn!("invalid option declaration : {}", line);
+                        }
+                    }
+                    _ => (),
+                }
+
+                handshake.lines.push(line);
+            }
+            Some(_) => {
+                if handshake.banner.len() == config.banner_limit {
+                    return Err(HandshakeError::BannerLimit(config.banner_limit));
+                }
+
+                if log_enabled!(Level::Debug) {
+                    debug!("skipping preamble line : {}", line);
+                }
+
+                // nobody may be subscribed
+                let _ = etx.send(UciEvent::Banner(line.clone()));
+
+                handshake.banner.push(line);
+            }
+            _ => (),
+        }
+    }
+}
+
+/// issue isready and wait for readyok, all lines the engine sent before are read by then
+async fn sync_ready<W>(
+    stdin: &mut W,
+    transcript: &std::sync::Mutex<Transcript>,
+    ready_rx: &mut mpsc::UnboundedReceiver<String>,
+    timeout: Duration,
+) -> Result<(), HandshakeError>
+where
+    W: AsyncWrite + Unpin,
+{
+    write_command(stdin, transcript, "isready").await;
+
+    match tokio::time::timeout(timeout, ready_rx.recv()).await {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(HandshakeError::Closed),
+        _ => Err(HandshakeError::Timeout(timeout)),
+    }
+}
+
+/// registration command for the given name and code
+fn register_command(name: &str, code: &str) -> String {
+    format!("register name {} code {}", name, code)
+}
+
+/// send go job with a fresh result channel to the go task
+fn send_go_job(gtx: &mpsc::UnboundedSender<GoJob>, go_job: GoJob) -> oneshot::Receiver<GoResult> {
+    let mut go_job = go_job;
+
+    let (rtx, rrx): (oneshot::Sender<GoResult>, oneshot::Receiver<GoResult>) = oneshot::channel();
+
+    go_job.rtx = Some(rtx);
+
+    let send_result = gtx.send(go_job);
+
+    if log_enabled!(Level::Debug) {
+        debug!("send go job result {:?}", send_result);
+    }
+
+    rrx
+}
+
+/// uci engine
+pub struct UciEngine {
+    gtx: mpsc::UnboundedSender<GoJob>,
+    pub ai: std::sync::Arc<std::sync::Mutex<AnalysisInfo>>,
+    pub atx: std::sync::Arc<broadcast::Sender<AnalysisInfo>>,
+    /// engine events other than analysis infos, currently the banner lines
+    /// skipped during the handshake and trend changes of the current search
+    pub etx: std::sync::Arc<broadcast::Sender<UciEvent>>,
+    /// background tasks owned by the engine
+    tasks: std::sync::Mutex<Vec<tokio::task::JoinHandle<()>>>,
+    /// last measured isready / readyok round trip time
+    latency: std::sync::Arc<std::sync::Mutex<Option<Duration>>>,
+    /// periodic latency probe task
+    probe: std::sync::Mutex<Option<tokio::task::JoinHandle<()>>>,
+    /// use measured latency as move overhead
+    auto_move_overhead: std::sync::atomic::AtomicBool,
+    /// search generation of the last go command sent
+    generation: std::sync::Arc<AtomicU64>,
+    /// protocol errors not taken yet
+    protocol_errors: std::sync::Arc<std::sync::Mutex<Vec<ProtocolError>>>,
+    /// last successful handshake
+    handshake: std::sync::Mutex<Option<Handshake>>,
+    /// last copy protection status reported
+    copyprotection: std::sync::Arc<std::sync::Mutex<Option<CheckStatus>>>,
+    /// last registration status reported
+    registration: std::sync::Arc<std::sync::Mutex<Option<CheckStatus>>>,
+    /// analysis profiles by name
+    profiles: std::sync::Mutex<HashMap<String, AnalysisProfile>>,
+    /// last value sent for each option, by lower case name, in job order
+    sent_options: std::sync::Mutex<HashMap<String, String>>,
+    /// normalize scores to the point of view of white
+    white_pov: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    /// side to move of the current search if its scores are normalized
+    pov: std::sync::Arc<std::sync::Mutex<Option<Color>>>,
+    /// best line per depth of the current search
+    history: std::sync::Arc<std::sync::Mutex<AnalysisHistory>>,
+    /// implausible values sent during the current search
+    suspects: std::sync::Arc<std::sync::Mutex<Vec<SuspectField>>>,
+    /// keep the best line per depth
+    retain_history: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    /// trend of the score of the current search
+    trend: std::sync::Arc<std::sync::Mutex<TrendTracker>>,
+}
+
+/// uci engine implementation
+impl UciEngine {
     pub fn new(path: impl Display) -> std::sync::Arc<UciEngine> {
         Self::new_with_args(path, &[] as &[&str])
     }
@@ -303,16 +1279,40 @@ impl UciEngine {
     /// create new uci engine
     pub fn new_with_args<T>(path: T, args: &[impl ToString]) -> std::sync::Arc<UciEngine>
     where
-        T: core::fmt::Display
+        T: core::fmt::Display,
     {
-        // you can use anything that can be converted to string as path
-        let path = path.to_string();
+        Self::new_with_config(EngineConfig::new(path).args(args))
+    }
+
+    /// create new uci engine from engine process configuration
+    pub fn new_with_config(config: EngineConfig) -> std::sync::Arc<UciEngine> {
+        let path = config.path.clone();
+
+        let mut command = Command::new(path.as_str());
+
+        command
+            .args(config.args.iter().map(|arg| config.expand(arg)))
+            .envs(
+                config
+                    .envs
+                    .iter()
+                    .map(|(key, value)| (key, config.expand(value))),
+            );
+
+        if config.c_locale {
+            // locale formatted numbers ( 1.234,5 ) would not parse
+            command.env("LC_ALL", "C");
+        }
+
+        if let Some(current_dir) = &config.current_dir {
+            command.current_dir(config.expand(current_dir));
+        }
 
         // spawn engine process
-        let mut child = Command::new(path.as_str())
-            .args(args.iter().map(|s| s.to_string()))
+        let mut child = command
             .stdout(Stdio::piped())
             .stdin(Stdio::piped())
+            .kill_on_drop(true)
             .spawn()
             .expect("failed to spawn engine");
 
@@ -328,13 +1328,7 @@ impl UciEngine {
             .take()
             .expect("child did not have a handle to stdin");
 
-        // stdout reader
-        let reader = BufReader::new(stdout).lines();
-
-        // channel for receiving bestmove result
-        let (tx, rx) = mpsc::unbounded_channel::<String>();
-
-        tokio::spawn(async move {
+        let wait_task = tokio::spawn(async move {
             // run engine process and wait for exit code
             let status = child
                 .wait()
@@ -346,20 +1340,114 @@ impl UciEngine {
             }
         });
 
+        if log_enabled!(Level::Info) {
+            info!("spawned uci engine : {}", path);
+        }
+
+        let engine = Self::start(stdout, stdin, vec![wait_task]);
+
+        for line in &config.init {
+            drop(engine.go(GoJob::new().custom(config.expand(line))));
+        }
+
+        engine
+    }
+
+    /// create new uci engine talking to an already running engine
+    /// through the given output reader and input writer,
+    /// output may arrive in arbitrary chunks ( e.g. from a network transport ),
+    /// it is assembled into lines and delivered in the order received
+    pub fn new_with_io<R, W>(reader: R, writer: W) -> std::sync::Arc<UciEngine>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        Self::start(reader, writer, vec![])
+    }
+
+    /// start reading engine output and processing go jobs
+    pub(crate) fn start<R, W>(
+        stdout: R,
+        stdin: W,
+        mut tasks: Vec<tokio::task::JoinHandle<()>>,
+    ) -> std::sync::Arc<UciEngine>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        // stdout reader
+        let reader = BufReader::new(stdout).lines();
+
+        // channel for receiving bestmove result
+        let (tx, rx) = mpsc::unbounded_channel::<String>();
+
+        // channel for receiving readyok
+        let (ready_tx, ready_rx) = mpsc::unbounded_channel::<String>();
+
+        // channel for receiving lines that are neither info, bestmove nor readyok
+        let (line_tx, line_rx) = mpsc::unbounded_channel::<String>();
+
+        let copyprotection = std::sync::Arc::new(std::sync::Mutex::new(None));
+
+        let copyprotection_clone = copyprotection.clone();
+
+        let registration = std::sync::Arc::new(std::sync::Mutex::new(None));
+
+        let registration_clone = registration.clone();
+
+        let transcript = std::sync::Arc::new(std::sync::Mutex::new(Transcript::new()));
+
+        let transcript_clone = transcript.clone();
+
         let ai = std::sync::Arc::new(std::sync::Mutex::new(AnalysisInfo::new()));
 
         let ai_clone = ai.clone();
 
+        let pov = std::sync::Arc::new(std::sync::Mutex::new(None));
+
+        let pov_clone = pov.clone();
+
+        let history = std::sync::Arc::new(std::sync::Mutex::new(AnalysisHistory::new()));
+
+        let history_clone = history.clone();
+
+        let suspects = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+
+        let suspects_clone = suspects.clone();
+
+        let retain_history = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+        let retain_history_clone = retain_history.clone();
+
+        let trend = std::sync::Arc::new(std::sync::Mutex::new(TrendTracker::new(TREND_WINDOW)));
+
+        let trend_clone = trend.clone();
+
         let (atx, _) = broadcast::channel::<AnalysisInfo>(20);
 
         let atx = std::sync::Arc::new(atx);
 
         let atx_clone = atx.clone();
 
-        tokio::spawn(async move {
+        let (etx, _) = broadcast::channel::<UciEvent>(EVENT_CAPACITY);
+
+        let etx = std::sync::Arc::new(etx);
+
+        let etx_clone = etx.clone();
+
+        let read_task = tokio::spawn(async move {
             let mut reader = reader;
             let ai = ai_clone;
+            let pov = pov_clone;
+            let history = history_clone;
+            let suspects = suspects_clone;
+            let retain_history = retain_history_clone;
+            let trend = trend_clone;
             let atx = atx_clone;
+            let etx = etx_clone;
+            let transcript = transcript_clone;
+            let copyprotection = copyprotection_clone;
+            let registration = registration_clone;
 
             let test_parse_info = env_true("TEST_PARSE_INFO");
             let mut num_lines: usize = 0;
@@ -372,20 +1460,36 @@ impl UciEngine {
                         if let Some(line) = line_opt {
                             num_lines += 1;
 
+                            transcript.lock().unwrap().push(format!("< {}", line));
+
                             if log_enabled!(Level::Debug) {
                                 debug!("uci engine out ( {} ) : {}", num_lines, line);
                             }
 
-                            let mut is_bestmove = line.len() >= 8;
+                            let is_bestmove = line.split_whitespace().next() == Some("bestmove");
 
-                            if is_bestmove {
-                                is_bestmove = &line[0..8] == "bestmove";
+                            let is_readyok = line.trim() == "readyok";
+
+                            let is_info = line.split_whitespace().next() == Some("info");
+
+                            // the shared analysis info keeps fields of earlier lines,
+                            // history and trend need the line on its own
+                            let mut line_ai = AnalysisInfo::new();
+
+                            if is_info && line_ai.parse(&line).is_ok() {
+                                if retain_history.load(Ordering::Relaxed) {
+                                    history.lock().unwrap().insert(line_ai);
+                                }
+
+                                if let Some(changed) = trend.lock().unwrap().push(&line_ai) {
+                                    let _ = etx.send(UciEvent::Trend(changed));
+                                }
                             }
 
                             {
                                 let mut ai = ai.lock().unwrap();
 
-                                let parse_result = ai.parse(line.to_owned());
+                                let parse_result = ai.parse_checked(&line);
 
                                 if is_bestmove {
                                     ai.done = true;
@@ -393,12 +1497,23 @@ impl UciEngine {
 
                                 debug!("parse result {:?} , ai {:?}", parse_result, ai);
 
+                                if let Ok(found) = &parse_result {
+                                    let mut suspects = suspects.lock().unwrap();
+
+                                    let room = MAX_SUSPECTS.saturating_sub(suspects.len());
+
+                                    suspects.extend(found.iter().take(room));
+                                }
+
                                 if parse_result.is_ok() {
                                     ok_lines += 1;
 
-                                    let send_result = atx.send(*ai);
+                                    // readyok carries no analysis
+                                    if !is_readyok {
+                                        let send_result = atx.send(pov_ai(*ai, &pov));
 
-                                    debug!("send ai result {:?}", send_result);
+                                        debug!("send ai result {:?}", send_result);
+                                    }
                                 } else {
                                     failed_lines += 1;
 
@@ -422,6 +1537,36 @@ impl UciEngine {
                                 if log_enabled!(Level::Debug) {
                                     debug!("send bestmove result {:?}", send_result);
                                 }
+                            } else if is_readyok {
+                                let send_result = ready_tx.send(line);
+
+                                if log_enabled!(Level::Debug) {
+                                    debug!("send readyok result {:?}", send_result);
+                                }
+                            } else if line.split_whitespace().next() != Some("info") {
+                                let mut words = line.split_whitespace();
+
+                                // statuses are kept as they arrive, so they are known once
+                                // readyok to a later isready is received
+                                let status = match (words.next(), words.next()) {
+                                    (Some("copyprotection"), Some(status)) => {
+                                        Some((&copyprotection, CheckStatus::parse(status)))
+                                    }
+                                    (Some("registration"), Some(status)) => {
+                                        Some((&registration, CheckStatus::parse(status)))
+                                    }
+                                    _ => None,
+                                };
+
+                                if let Some((check, Some(status))) = status {
+                                    *check.lock().unwrap() = Some(status);
+                                }
+
+                                let send_result = line_tx.send(line);
+
+                                if log_enabled!(Level::Debug) {
+                                    debug!("send line result {:?}", send_result);
+                                }
                             }
                         } else {
                             if log_enabled!(Level::Debug) {
@@ -451,115 +1596,1885 @@ impl UciEngine {
 
         let ai_clone = ai.clone();
 
-        tokio::spawn(async move {
+        let latency = std::sync::Arc::new(std::sync::Mutex::new(None));
+
+        let latency_clone = latency.clone();
+
+        let generation = std::sync::Arc::new(AtomicU64::new(0));
+
+        let generation_clone = generation.clone();
+
+        let white_pov = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+        let white_pov_clone = white_pov.clone();
+
+        let pov_clone = pov.clone();
+
+        let history_clone = history.clone();
+
+        let suspects_clone = suspects.clone();
+
+        let trend_clone = trend.clone();
+
+        let etx_clone = etx.clone();
+
+        let protocol_errors = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+
+        let protocol_errors_clone = protocol_errors.clone();
+
+        let copyprotection_clone = copyprotection.clone();
+
+        let registration_clone = registration.clone();
+
+        let go_task = tokio::spawn(async move {
             let mut stdin = stdin;
             let mut grx = grx;
             let mut rx = rx;
+            let mut ready_rx = ready_rx;
+            let mut line_rx = line_rx;
+            let copyprotection = copyprotection_clone;
+            let registration = registration_clone;
             let ai = ai_clone;
+            let latency = latency_clone;
+            let generation = generation_clone;
+            let white_pov = white_pov_clone;
+            let pov = pov_clone;
+            let history = history_clone;
+            let suspects = suspects_clone;
+            let trend = trend_clone;
+            let etx = etx_clone;
+            let protocol_errors = protocol_errors_clone;
+            let mut job: u64 = 0;
+            let mut search_ended = false;
+
+            'jobs: while let Some(go_job) = grx.recv().await {
+                job += 1;
 
-            while let Some(go_job) = grx.recv().await {
                 if log_enabled!(Level::Debug) {
-                    debug!("received go job {:?}", go_job);
+                    debug!("received go job {} {:?}", job, go_job);
                 }
 
-                for command in go_job.to_commands() {
-                    let command = format!("{}\n", command);
+                // responses nobody waited for must not answer this job,
+                // a search that ended may still be followed by a stray bestmove,
+                // so the drain is repeated once readyok to an isready proves
+                // that all output of the search was read
+                let mut barrier = search_ended && go_job.custom_command.is_none();
+
+                search_ended = false;
+
+                loop {
+                    while let Ok(line) = rx.try_recv().or_else(|_| ready_rx.try_recv()) {
+                        // orphans are expected after stop races, they are ignored
+                        if log_enabled!(Level::Warn) {
+                            warn!(
+                                "ignoring unsolicited engine response {} before job {}",
+                                line, job
+                            );
+                        }
 
-                    if log_enabled!(Level::Debug) {
-                        debug!("issuing engine command : {}", command);
+                        let mut protocol_errors = protocol_errors.lock().unwrap();
+
+                        if protocol_errors.len() < MAX_PROTOCOL_ERRORS {
+                            protocol_errors.push(ProtocolError::Unsolicited {
+                                line,
+                                job,
+                                transcript: transcript.lock().unwrap().lines(),
+                            });
+                        }
+                    }
+
+                    if !barrier {
+                        break;
                     }
 
-                    let write_result = stdin.write_all(command.as_bytes()).await;
+                    barrier = false;
 
-                    if log_enabled!(Level::Debug) {
-                        debug!("write result {:?}", write_result);
+                    write_command(&mut stdin, &transcript, "isready").await;
+
+                    match tokio::time::timeout(BARRIER_TIMEOUT, ready_rx.recv()).await {
+                        Ok(Some(_)) => (),
+                        Ok(None) => {
+                            if log_enabled!(Level::Error) {
+                                error!("engine output ended while waiting for readyok");
+                            }
+
+                            break 'jobs;
+                        }
+                        // a late readyok is drained as unsolicited before the next job
+                        _ => {
+                            if log_enabled!(Level::Warn) {
+                                warn!(
+                                    "no readyok within {:?}, issuing job {}",
+                                    BARRIER_TIMEOUT, job
+                                );
+                            }
+
+                            break;
+                        }
                     }
                 }
 
-                if go_job.custom_command.is_none() && (!go_job.ponder) {
-                    {
-                        let mut ai = ai.lock().unwrap();
-
-                        *ai = AnalysisInfo::new();
+                if go_job.handshake.is_none() {
+                    // other lines only matter during the handshake
+                    while let Ok(line) = line_rx.try_recv() {
+                        if log_enabled!(Level::Debug) {
+                            debug!("ignoring engine line : {}", line);
+                        }
                     }
+                }
 
-                    let recv_result = rx.recv().await.unwrap();
+                if go_job.issues_go() {
+                    // reset before sending go, so that no line of the new search is lost
+                    let mut ai = ai.lock().unwrap();
 
-                    if log_enabled!(Level::Debug) {
-                        debug!("recv result {:?}", recv_result);
-                    }
+                    *ai = AnalysisInfo::new();
 
-                    let parts: Vec<&str> = recv_result.split(" ").collect();
+                    ai.generation = generation.fetch_add(1, Ordering::SeqCst) + 1;
 
-                    let send_ai: AnalysisInfo;
+                    history.lock().unwrap().reset();
 
-                    {
-                        let ai = ai.lock().unwrap();
+                    suspects.lock().unwrap().clear();
 
-                        send_ai = *ai;
-                    }
+                    trend.lock().unwrap().reset();
 
-                    let mut go_result = GoResult {
-                        bestmove: None,
-                        ponder: None,
-                        ai: send_ai,
+                    *pov.lock().unwrap() = match white_pov.load(Ordering::Relaxed) {
+                        true => go_job.side_to_move(),
+                        _ => None,
                     };
+                }
 
-                    if parts.len() > 1 {
-                        go_result.bestmove = Some(parts[1].to_string());
-                    }
-
-                    if parts.len() > 3 {
-                        go_result.ponder = Some(parts[3].to_string());
-                    }
+                let issued = Instant::now();
 
-                    let send_result = go_job.rtx.unwrap().send(go_result);
+                for command in go_job.to_commands() {
+                    write_command(&mut stdin, &transcript, &command).await;
+                }
 
-                    if log_enabled!(Level::Debug) {
-                        debug!("result of send go result {:?}", send_result);
-                    }
+                if go_job.custom_command.as_deref() == Some("quit") {
+                    // dropping stdin lets the engine see end of input even if it ignores quit
+                    break;
                 }
-            }
-        });
 
-        if log_enabled!(Level::Info) {
-            info!("spawned uci engine : {}", path);
-        }
+                if let Some(config) = go_job.handshake.clone() {
+                    let mut handshake_result = read_handshake(&mut line_rx, &config, &etx).await;
 
-        std::sync::Arc::new(UciEngine {
-            gtx: gtx,
-            ai: ai,
-            atx: atx,
-        })
-    }
+                    // without uciok there is no check phase to wait for
+                    if let Some(handshake) = handshake_result.as_mut().ok().filter(|h| !h.degraded)
+                    {
+                        // copy protection and registration are checked after uciok
+                        let mut sync_result =
+                            sync_ready(&mut stdin, &transcript, &mut ready_rx, config.timeout)
+                                .await;
+
+                        if sync_result.is_ok()
+                            && (*registration.lock().unwrap() == Some(CheckStatus::Error))
+                        {
+                            let command = match &config.register {
+                                Some((name, code)) => register_command(name, code),
+                                _ => "register later".to_string(),
+                            };
+
+                            // register later is not answered, the error stays reported
+                            if config.register.is_some() {
+                                *registration.lock().unwrap() = None;
+                            }
 
-    /// get analysis info
-    pub fn get_ai(&self) -> AnalysisInfo {
-        let ai = self.ai.lock().unwrap();
+                            write_command(&mut stdin, &transcript, &command).await;
 
-        *ai
-    }
+                            sync_result =
+                                sync_ready(&mut stdin, &transcript, &mut ready_rx, config.timeout)
+                                    .await;
+                        }
 
-    /// issue go command
-    pub fn go(&self, go_job: GoJob) -> oneshot::Receiver<GoResult> {
-        let mut go_job = go_job;
+                        handshake.copyprotection = *copyprotection.lock().unwrap();
+                        handshake.registration = *registration.lock().unwrap();
 
-        let (rtx, rrx): (oneshot::Sender<GoResult>, oneshot::Receiver<GoResult>) =
-            oneshot::channel();
+                        if let Err(err) = sync_result {
+                            handshake_result = Err(err);
+                        }
+                    }
 
-        go_job.rtx = Some(rtx);
+                    if log_enabled!(Level::Debug) {
+                        debug!("handshake result {:?}", handshake_result);
+                    }
 
-        let send_result = self.gtx.send(go_job);
+                    let closed = handshake_result == Err(HandshakeError::Closed);
 
-        if log_enabled!(Level::Debug) {
-            debug!("send go job result {:?}", send_result);
-        }
+                    if let Some(htx) = go_job.htx {
+                        let send_result = htx.send(handshake_result);
 
-        rrx
-    }
+                        if log_enabled!(Level::Debug) {
+                            debug!("result of send handshake result {:?}", send_result);
+                        }
+                    }
 
-    /// quit engine
-    pub fn quit(&self) {
-        self.go(GoJob::new().custom("quit"));
-    }
+                    if closed {
+                        break;
+                    }
+
+                    continue;
+                }
+
+                if go_job.isready {
+                    if ready_rx.recv().await.is_none() {
+                        if log_enabled!(Level::Error) {
+                            error!("engine output ended while waiting for readyok");
+                        }
+
+                        break;
+                    }
+
+                    let elapsed = issued.elapsed();
+
+                    if log_enabled!(Level::Debug) {
+                        debug!("engine latency {:?}", elapsed);
+                    }
+
+                    *latency.lock().unwrap() = Some(elapsed);
+
+                    let go_result = GoResult {
+                        bestmove: None,
+                        ponder: None,
+                        ai: *ai.lock().unwrap(),
+                        history: history.lock().unwrap().clone(),
+                        suspects: suspects.lock().unwrap().clone(),
+                        verified: false,
+                    };
+
+                    if let Some(rtx) = go_job.rtx {
+                        let send_result = rtx.send(go_result);
+
+                        if log_enabled!(Level::Debug) {
+                            debug!("result of send isready result {:?}", send_result);
+                        }
+                    }
+
+                    continue;
+                }
+
+                if go_job.custom_command.is_none() && (!go_job.ponder) {
+                    if !go_job.issues_go() {
+                        // ponderhit and pondermiss continue the search of the ponder go
+                        let mut ai = ai.lock().unwrap();
+
+                        let generation = ai.generation;
+
+                        *ai = AnalysisInfo::new();
+
+                        ai.generation = generation;
+                    }
+
+                    let recv_result = match rx.recv().await {
+                        Some(recv_result) => recv_result,
+                        _ => {
+                            // engine output ended, dropping the job lets the caller see the failure
+                            if log_enabled!(Level::Error) {
+                                error!("engine output ended while waiting for bestmove");
+                            }
+
+                            break;
+                        }
+                    };
+
+                    if log_enabled!(Level::Debug) {
+                        debug!("recv result {:?}", recv_result);
+                    }
+
+                    search_ended = true;
+
+                    let parts: Vec<&str> = recv_result.split_whitespace().collect();
+
+                    let send_ai: AnalysisInfo;
+
+                    {
+                        let ai = ai.lock().unwrap();
+
+                        send_ai = pov_ai(*ai, &pov);
+                    }
+
+                    let mut go_result = GoResult {
+                        bestmove: None,
+                        ponder: None,
+                        ai: send_ai,
+                        history: pov_history(&history.lock().unwrap(), &pov),
+                        suspects: suspects.lock().unwrap().clone(),
+                        verified: false,
+                    };
+
+                    if parts.len() > 1 {
+                        go_result.bestmove = Some(parts[1].to_string());
+                    }
+
+                    if parts.len() > 3 {
+                        go_result.ponder = Some(parts[3].to_string());
+                    }
+
+                    if let Some(rtx) = go_job.rtx {
+                        let send_result = rtx.send(go_result);
+
+                        if log_enabled!(Level::Debug) {
+                            debug!("result of send go result {:?}", send_result);
+                        }
+                    }
+                }
+            }
+        });
+
+        tasks.push(read_task);
+        tasks.push(go_task);
+
+        std::sync::Arc::new(UciEngine {
+            gtx,
+            ai,
+            atx,
+            etx,
+            tasks: std::sync::Mutex::new(tasks),
+            latency,
+            probe: std::sync::Mutex::new(None),
+            auto_move_overhead: std::sync::atomic::AtomicBool::new(false),
+            generation,
+            protocol_errors,
+            handshake: std::sync::Mutex::new(None),
+            copyprotection,
+            registration,
+            profiles: std::sync::Mutex::new(HashMap::new()),
+            sent_options: std::sync::Mutex::new(HashMap::new()),
+            white_pov,
+            pov,
+            history,
+            suspects,
+            retain_history,
+            trend,
+        })
+    }
+
+    /// get analysis info
+    pub fn get_ai(&self) -> AnalysisInfo {
+        let ai = self.ai.lock().unwrap();
+
+        pov_ai(*ai, &self.pov)
+    }
+
+    /// get best line per depth of the current or last search,
+    /// empty unless history is retained ( see set_retain_history )
+    pub fn get_history(&self) -> AnalysisHistory {
+        pov_history(&self.history.lock().unwrap(), &self.pov)
+    }
+
+    /// physically implausible values the engine sent during the current or last search
+    pub fn suspects(&self) -> Vec<SuspectField> {
+        self.suspects.lock().unwrap().clone()
+    }
+
+    /// trend of the score of the current or last search, from the point of view
+    /// of the side to move, None until two depths were completed
+    pub fn trend(&self) -> Option<Trend> {
+        self.trend.lock().unwrap().trend()
+    }
+
+    /// issue go command
+    pub fn go(&self, go_job: GoJob) -> oneshot::Receiver<GoResult> {
+        let mut go_job = go_job;
+
+        if go_job.issues_go()
+            && go_job.move_overhead.is_none()
+            && self.auto_move_overhead.load(Ordering::Relaxed)
+        {
+            if let Some(latency) = self.latency() {
+                go_job = go_job.move_overhead(latency.as_millis() as usize);
+            }
+        }
+
+        if go_job.issues_go() {
+            go_job = go_job.move_overhead_option(&self.options());
+        }
+
+        {
+            let mut sent_options = self.sent_options.lock().unwrap();
+
+            for (name, value) in go_job.set_options() {
+                sent_options.insert(name.to_lowercase(), value);
+            }
+        }
+
+        send_go_job(&self.gtx, go_job)
+    }
+
+    /// issue uci and wait for uciok, skipping preamble lines
+    /// such as startup banners within the configured limits
+    pub async fn handshake(&self, config: HandshakeConfig) -> Result<Handshake, HandshakeError> {
+        let (htx, hrx) = oneshot::channel();
+
+        let mut go_job = GoJob::new();
+
+        go_job.handshake = Some(config);
+        go_job.htx = Some(htx);
+
+        drop(self.go(go_job));
+
+        let handshake_result = hrx.await.unwrap_or(Err(HandshakeError::Closed));
+
+        if let Ok(handshake) = &handshake_result {
+            *self.handshake.lock().unwrap() = Some(handshake.clone());
+        }
+
+        handshake_result
+    }
+
+    /// engine identification from the last successful handshake,
+    /// None before the handshake
+    pub fn engine_id(&self) -> Option<EngineId> {
+        self.handshake
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|handshake| handshake.id.clone())
+    }
+
+    /// search go job, then search the position after the best move to the same depth,
+    /// i.e. one ply deeper, and set verified on the result if the score does not drop
+    /// by more than margin_cp centipawns, guarding against horizon effects
+    /// in short searches, None if the engine is gone
+    ///
+    /// the go job has to specify the position, otherwise the result is never verified
+    pub async fn go_verified(&self, go_job: GoJob, margin_cp: i32) -> Option<GoResult> {
+        let position = go_job.position();
+
+        let side = go_job.side_to_move();
+
+        let mut go_result = self.go(go_job).await.ok()?;
+
+        let bestmove = match go_result.best_move() {
+            Some(BestMove {
+                bestmove: Some(bestmove),
+                ..
+            }) => bestmove,
+            _ => return Some(go_result),
+        };
+
+        let position = match position {
+            Some(position) if go_result.ai.depth > 0 => position,
+            _ => return Some(go_result),
+        };
+
+        let verification_job = position
+            .push_move(&bestmove)
+            .go_opt("depth", go_result.ai.depth);
+
+        let verification = self.go(verification_job).await.ok()?;
+
+        let score = self.side_to_move_score(go_result.ai.score, side);
+
+        // the verification score is from the point of view of the opponent
+        let verification_score =
+            self.side_to_move_score(verification.ai.score, side.map(Color::opposite));
+
+        go_result.verified =
+            (-verification_score).score_value() >= score.score_value() - i64::from(margin_cp);
+
+        Some(go_result)
+    }
+
+    /// walk the line of uci moves from the position of the go job, searching every position
+    /// along it with the go options of the job as limit, and report how much each move
+    /// loses against the best move and where the line first loses more than margin_cp
+    /// centipawns, None if the job specifies no position or the engine is gone
+    pub async fn explore(
+        &self,
+        go_job: GoJob,
+        line: &[&str],
+        margin_cp: i32,
+    ) -> Option<LineExploration> {
+        let mut position = go_job.position()?;
+
+        let mut searches = vec![];
+
+        for i in 0..=line.len() {
+            let mut search_job = position.position()?;
+
+            search_job.go_options = go_job.go_options.clone();
+
+            let side = search_job.side_to_move();
+
+            let go_result = self.go(search_job).await.ok()?;
+
+            let score = self.side_to_move_score(go_result.ai.score, side);
+
+            searches.push((go_result.bestmove, score));
+
+            if let Some(uci) = line.get(i) {
+                position = position.push_move(uci);
+            }
+        }
+
+        let mut steps = vec![];
+
+        for (i, uci) in line.iter().enumerate() {
+            let (bestmove, best_score) = searches[i].clone();
+
+            // the next search is from the point of view of the opponent
+            let score = -searches[i + 1].1;
+
+            let loss_cp = match bestmove.as_deref() {
+                Some(bestmove) if bestmove == *uci => 0,
+                _ => (best_score.score_value() - score.score_value()).max(0),
+            };
+
+            steps.push(LineStep {
+                uci: uci.to_string(),
+                bestmove,
+                best_score,
+                score,
+                loss_cp,
+            });
+        }
+
+        let wrong_at = steps
+            .iter()
+            .position(|step| step.loss_cp > i64::from(margin_cp));
+
+        Some(LineExploration { steps, wrong_at })
+    }
+
+    /// search the position of the go job with MultiPV 2 and return whether the best move
+    /// is an only move, i.e. the second best line scores more than margin_cp centipawns
+    /// worse or there is no second line, None if the engine declares no MultiPV option
+    /// in the last successful handshake, is gone, found no move or infos were missed,
+    /// MultiPV is set back to its previous value afterwards ( see go_profile )
+    pub async fn is_only_move(&self, go_job: GoJob, margin_cp: i32) -> Option<bool> {
+        let declared = self.options();
+
+        let name = OptionAliases::new().resolve("MultiPV", &declared)?;
+
+        let profile = AnalysisProfile::new().uci_opt(name, 2);
+
+        let go_job = go_job.uci_opt(name, 2);
+
+        let applied = go_job.uci_options.clone();
+
+        let sent = self.sent_options.lock().unwrap().clone();
+
+        let mut arx = self.atx.subscribe();
+
+        let side = go_job.side_to_move();
+
+        let mut rrx = self.go(go_job);
+
+        for command in profile.revert_commands(&declared, &sent, &applied) {
+            drop(self.go(GoJob::new().custom(command)));
+        }
+
+        let mut infos = vec![];
+
+        let mut lagged = false;
+
+        let go_result = loop {
+            tokio::select! {
+                go_result = &mut rrx => break go_result.ok()?,
+                ai = arx.recv() => match ai {
+                    Ok(ai) => infos.push(ai),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        if log_enabled!(Level::Warn) {
+                            warn!("only move check missed {} infos", skipped);
+                        }
+
+                        lagged = true;
+                    }
+                    _ => (),
+                },
+            }
+        };
+
+        // infos sent before the result was received may still be queued
+        loop {
+            match arx.try_recv() {
+                Ok(ai) => infos.push(ai),
+                Err(broadcast::error::TryRecvError::Lagged(_)) => lagged = true,
+                _ => break,
+            }
+        }
+
+        // a missed line of the second best move would pass for an only move
+        if lagged {
+            return None;
+        }
+
+        let mut mpv = MultiPvAnalysis::new();
+
+        for ai in infos {
+            if ai.generation == go_result.ai.generation {
+                mpv.insert(ai);
+            }
+        }
+
+        let best = self.side_to_move_score(mpv.get(1)?.score, side);
+
+        match mpv.get(2) {
+            Some(second) => {
+                let second = self.side_to_move_score(second.score, side);
+
+                Some(best.score_value() - second.score_value() > i64::from(margin_cp))
+            }
+            _ => Some(true),
+        }
+    }
+
+    /// score of a search from the given side to move back to the point of view
+    /// of the side to move, if it was normalized to the point of view of white
+    fn side_to_move_score(&self, score: Score, side: Option<Color>) -> Score {
+        match side {
+            // normalizing is its own inverse
+            Some(side) if self.white_pov.load(Ordering::Relaxed) => score.to_white_pov(side),
+            _ => score,
+        }
+    }
+
+    /// last copy protection status reported by the engine,
+    /// None if the engine did not report any
+    pub fn copyprotection(&self) -> Option<CheckStatus> {
+        *self.copyprotection.lock().unwrap()
+    }
+
+    /// last registration status reported by the engine,
+    /// None if the engine did not report any
+    pub fn registration(&self) -> Option<CheckStatus> {
+        *self.registration.lock().unwrap()
+    }
+
+    /// register engine with name and code, returns the registration status reported
+    /// by the engine, None if the engine is gone or did not report a status
+    pub async fn register_name_code<N, C>(&self, name: N, code: C) -> Option<CheckStatus>
+    where
+        N: core::fmt::Display,
+        C: core::fmt::Display,
+    {
+        let command = register_command(&format!("{}", name), &format!("{}", code));
+
+        // a status reported earlier must not pass for the outcome of this registration
+        *self.registration.lock().unwrap() = None;
+
+        drop(self.go(GoJob::new().custom(command)));
+
+        // readyok arrives after the engine reported the outcome of the registration
+        self.ping().await?;
+
+        self.registration()
+    }
+
+    /// options declared by the engine in the last successful handshake
+    pub fn options(&self) -> Vec<UciOptionDef> {
+        match &*self.handshake.lock().unwrap() {
+            Some(handshake) => handshake.options.clone(),
+            _ => vec![],
+        }
+    }
+
+    /// add analysis profile under name, replacing any profile with the same name
+    pub fn add_profile<T>(&self, name: T, profile: AnalysisProfile)
+    where
+        T: core::fmt::Display,
+    {
+        self.profiles
+            .lock()
+            .unwrap()
+            .insert(format!("{}", name), profile);
+    }
+
+    /// issue go command with the settings of the named analysis profile,
+    /// its uci options are reverted once the search is done to the values sent before,
+    /// or the defaults declared in the last successful handshake for options not sent,
+    /// None if there is no profile with that name
+    pub fn go_profile(&self, name: &str, go_job: GoJob) -> Option<oneshot::Receiver<GoResult>> {
+        let profile = self.profiles.lock().unwrap().get(name)?.clone();
+
+        let go_job = profile.apply(go_job);
+
+        let applied = go_job.uci_options.clone();
+
+        let sent = self.sent_options.lock().unwrap().clone();
+
+        let rrx = self.go(go_job);
+
+        // jobs are processed in order, the reverts run right after the search
+        for command in profile.revert_commands(&self.options(), &sent, &applied) {
+            drop(self.go(GoJob::new().custom(command)));
+        }
+
+        Some(rrx)
+    }
+
+    /// set option given by unified key to value, under the name the engine declared it
+    /// in the last successful handshake, returns that name, None if the engine
+    /// declares no option for the key or no handshake was made
+    pub fn set_option<K, V>(&self, aliases: &OptionAliases, key: K, value: V) -> Option<String>
+    where
+        K: core::fmt::Display,
+        V: core::fmt::Display,
+    {
+        let options = self.options();
+
+        let name = aliases.resolve(&format!("{}", key), &options)?.to_string();
+
+        let command = format!("setoption name {} value {}", name, value);
+
+        drop(self.go(GoJob::new().custom(command)));
+
+        Some(name)
+    }
+
+    /// set the draw options declared in the last successful handshake according to the policy,
+    /// returns the options set as name value pairs, empty if the engine declares
+    /// no known draw option or no handshake was made
+    pub fn set_draw_policy(&self, policy: DrawPolicy) -> Vec<(String, String)> {
+        let options = policy.options(&self.options());
+
+        for (name, value) in &options {
+            let command = format!("setoption name {} value {}", name, value);
+
+            drop(self.go(GoJob::new().custom(command)));
+        }
+
+        options
+    }
+
+    /// search generation of the last go command sent to the engine,
+    /// analysis infos with a lower generation belong to earlier searches
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// take protocol errors detected since the last call ( at most MAX_PROTOCOL_ERRORS )
+    pub fn take_protocol_errors(&self) -> Vec<ProtocolError> {
+        std::mem::take(&mut *self.protocol_errors.lock().unwrap())
+    }
+
+    /// last measured isready / readyok round trip time, if any
+    pub fn latency(&self) -> Option<Duration> {
+        *self.latency.lock().unwrap()
+    }
+
+    /// issue isready, wait for readyok and return the measured round trip time,
+    /// None if the engine is gone
+    pub async fn ping(&self) -> Option<Duration> {
+        self.go(GoJob::new().isready()).await.ok()?;
+
+        self.latency()
+    }
+
+    /// measure latency periodically in the background, replacing any previous probe,
+    /// probes are queued behind running searches like any other job
+    pub fn probe_latency(&self, interval: Duration) {
+        let gtx = self.gtx.clone();
+
+        let probe_task = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+
+            loop {
+                interval.tick().await;
+
+                if send_go_job(&gtx, GoJob::new().isready()).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        if let Some(probe_task) = self.probe.lock().unwrap().replace(probe_task) {
+            probe_task.abort();
+        }
+    }
+
+    /// set whether go jobs without an explicit move overhead
+    /// should use the measured latency as move overhead,
+    /// useful for engines behind slow transports
+    pub fn set_auto_move_overhead(&self, enabled: bool) {
+        self.auto_move_overhead.store(enabled, Ordering::Relaxed);
+    }
+
+    /// set whether scores of searches started from now on are normalized to the point
+    /// of view of white, instead of the side to move, in broadcast analysis infos and
+    /// results, so that eval graphs do not flip sign every ply,
+    /// scores of go jobs without position are not normalized
+    pub fn set_white_pov(&self, enabled: bool) {
+        self.white_pov.store(enabled, Ordering::Relaxed);
+    }
+
+    /// keep the best line of every depth of each search ( disabled by default ),
+    /// returned in go results and by get_history
+    pub fn set_retain_history(&self, enabled: bool) {
+        self.retain_history.store(enabled, Ordering::Relaxed);
+    }
+
+    /// quit engine
+    pub fn quit(&self) {
+        self.go(GoJob::new().custom("quit"));
+    }
+
+    /// quit engine and wait for the engine process and all background tasks to finish
+    pub async fn shutdown(&self) {
+        if let Some(probe_task) = self.probe.lock().unwrap().take() {
+            probe_task.abort();
+        }
+
+        self.quit();
+
+        let tasks = std::mem::take(&mut *self.tasks.lock().unwrap());
+
+        for task in tasks {
+            let join_result = task.await;
+
+            if log_enabled!(Level::Debug) {
+                debug!("join engine task result {:?}", join_result);
+            }
+        }
+    }
+}
+
+/// abort background tasks that were not joined by shutdown,
+/// which also kills the engine process if it is still running
+impl Drop for UciEngine {
+    fn drop(&mut self) {
+        for task in self.tasks.get_mut().unwrap().drain(..) {
+            task.abort();
+        }
+
+        if let Some(probe_task) = self.probe.get_mut().unwrap().take() {
+            probe_task.abort();
+        }
+    }
+}
+
+#[test]
+fn move_overhead() {
+    let commands = GoJob::new()
+        .pos_startpos()
+        .go_opt("movetime", 1000)
+        .move_overhead(150)
+        .to_commands();
+
+    assert_eq!(commands, vec!["position startpos", "go movetime 850"]);
+
+    let commands = GoJob::new()
+        .go_opt("movetime", 100)
+        .move_overhead(500)
+        .to_commands();
+
+    assert_eq!(commands.last().unwrap(), "go movetime 1");
+
+    let go_job = GoJob::new()
+        .pos_startpos()
+        .tc(Timecontrol {
+            wtime: 60000,
+            winc: 1000,
+            btime: 30000,
+            binc: 1000,
+        })
+        .move_overhead(200);
+
+    // only the clock of the side to move is reduced
+    let go_command = go_job.to_commands().pop().unwrap();
+
+    for option in ["wtime 59800", "btime 30000", "winc 1000", "binc 1000"] {
+        assert!(go_command.contains(option), "{}", go_command);
+    }
+
+    let go_command = GoJob::new()
+        .pos_startpos()
+        .pos_moves("e2e4")
+        .go_opt("wtime", 60000)
+        .go_opt("btime", 30000)
+        .move_overhead(200)
+        .to_commands()
+        .pop()
+        .unwrap();
+
+    for option in ["wtime 60000", "btime 29800"] {
+        assert!(go_command.contains(option), "{}", go_command);
+    }
+
+    // the option is only sent when declared, under the declared name
+    let declared: Vec<UciOptionDef> =
+        ["option name MoveOverhead type spin default 10 min 0 max 5000"]
+            .iter()
+            .filter_map(UciOptionDef::parse)
+            .collect();
+
+    let go_job = go_job.move_overhead_option(&declared);
+
+    assert_eq!(
+        go_job.uci_options.get("MoveOverhead"),
+        Some(&"200".to_string())
+    );
+
+    // the engine compensates, the times are not reduced as well
+    let go_command = go_job.to_commands().pop().unwrap();
+
+    for option in ["wtime 60000", "btime 30000"] {
+        assert!(go_command.contains(option), "{}", go_command);
+    }
+
+    let go_job = GoJob::new().move_overhead(200).move_overhead_option(&[]);
+
+    assert!(go_job.uci_options.is_empty());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn engine_config_env() {
+    let engine = UciEngine::new_with_config(
+        EngineConfig::new("sh")
+            .arg("-c")
+            .arg(r#"while read cmd; do case $cmd in go*) echo "bestmove $MOVE ponder $LC_ALL";; esac; done"#)
+            .env("MOVE", "e2e4")
+            .c_locale(),
+    );
+
+    let go_result = engine.go(GoJob::new().pos_startpos()).await.unwrap();
+
+    assert_eq!(go_result.bestmove, Some("e2e4".to_string()));
+    assert_eq!(go_result.ponder, Some("C".to_string()));
+
+    engine.shutdown().await;
+}
+
+#[test]
+fn engine_config_templates() {
+    let config = EngineConfig::new("/opt/lc0/lc0")
+        .var("net", "t78.pb.gz")
+        .var("threads", 4);
+
+    assert_eq!(
+        config.expand("--weights={engine_dir}/{net}"),
+        "--weights=/opt/lc0/t78.pb.gz"
+    );
+    assert_eq!(
+        config.expand("--threads={threads} {unknown}"),
+        "--threads=4 {unknown}"
+    );
+
+    assert_eq!(
+        EngineConfig::new("no-such-engine-executable").expand("{engine_dir}/x"),
+        "./x"
+    );
+
+    #[cfg(unix)]
+    {
+        let dir = EngineConfig::new("sh").expand("{engine_dir}");
+
+        assert!(std::path::Path::new(&dir).join("sh").is_file());
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn engine_config_init() {
+    let engine = UciEngine::new_with_config(
+        EngineConfig::new("sh")
+            .arg("-c")
+            .arg(r#"while read cmd; do case $cmd in move*) m=${cmd#move };; go*) echo "bestmove $m ponder $1";; esac; done"#)
+            .arg("sh")
+            .arg("{ponder}")
+            .init("move {move}")
+            .var("move", "d2d4")
+            .var("ponder", "d7d5"),
+    );
+
+    let go_result = engine.go(GoJob::new().pos_startpos()).await.unwrap();
+
+    assert_eq!(go_result.bestmove, Some("d2d4".to_string()));
+    assert_eq!(go_result.ponder, Some("d7d5".to_string()));
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn search_generations() {
+    let engine = crate::mock::MockEngine::new()
+        .on_go(vec![crate::mock::MockStep::line("bestmove e2e4")])
+        .on_go(vec![
+            crate::mock::MockStep::line("info depth 1 pv d2d4"),
+            crate::mock::MockStep::line("bestmove d2d4"),
+        ])
+        .spawn();
+
+    let mut arx = engine.atx.subscribe();
+
+    assert_eq!(engine.generation(), 0);
+
+    let go_result = engine.go(GoJob::new().pos_startpos()).await.unwrap();
+
+    assert_eq!(go_result.ai.generation, 1);
+
+    let go_result = engine.go(GoJob::new().pos_startpos()).await.unwrap();
+
+    assert_eq!(go_result.ai.generation, 2);
+    assert_eq!(engine.generation(), 2);
+
+    let mut generations = vec![];
+
+    while let Ok(ai) = arx.try_recv() {
+        generations.push(ai.generation);
+    }
+
+    assert_eq!(generations, vec![1, 2, 2]);
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn unsolicited_response() {
+    let engine = crate::mock::MockEngine::new()
+        .on_go(vec![
+            crate::mock::MockStep::line("readyok"),
+            crate::mock::MockStep::line("bestmove e2e4"),
+        ])
+        .spawn();
+
+    let go_result = engine.go(GoJob::new().pos_startpos()).await.unwrap();
+
+    assert_eq!(go_result.bestmove, Some("e2e4".to_string()));
+
+    let go_result = engine.go(GoJob::new().pos_startpos()).await.unwrap();
+
+    assert_eq!(go_result.bestmove, Some("0000".to_string()));
+
+    let protocol_errors = engine.take_protocol_errors();
+
+    assert_eq!(protocol_errors.len(), 1);
+
+    match &protocol_errors[0] {
+        ProtocolError::Unsolicited { line, job, .. } => {
+            assert_eq!(line, "readyok");
+            assert_eq!(*job, 2);
+        }
+    }
+
+    assert!(protocol_errors[0]
+        .transcript()
+        .contains(&"< readyok".to_string()));
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn duplicate_bestmove() {
+    let engine = crate::mock::MockEngine::new()
+        .on_go(vec![
+            crate::mock::MockStep::line("bestmove e2e4"),
+            crate::mock::MockStep::delay_ms(20),
+            crate::mock::MockStep::line("bestmove e2e4"),
+        ])
+        .on_go(vec![crate::mock::MockStep::line("bestmove d2d4")])
+        .spawn();
+
+    let go_result = engine.go(GoJob::new().pos_startpos()).await.unwrap();
+
+    assert_eq!(go_result.bestmove, Some("e2e4".to_string()));
+
+    // the late duplicate must not answer the second search
+    let go_result = engine.go(GoJob::new().pos_startpos()).await.unwrap();
+
+    assert_eq!(go_result.bestmove, Some("d2d4".to_string()));
+
+    assert_eq!(engine.take_protocol_errors().len(), 1);
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn protocol_errors_capped() {
+    let mut steps = vec![crate::mock::MockStep::line("bestmove e2e4")];
+
+    steps.extend((0..(MAX_PROTOCOL_ERRORS + 5)).map(|_| crate::mock::MockStep::line("readyok")));
+
+    let engine = crate::mock::MockEngine::new().on_go(steps).spawn();
+
+    engine.go(GoJob::new().pos_startpos()).await.unwrap();
+
+    // let the stray lines arrive before the next job drains them
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    engine.go(GoJob::new().pos_startpos()).await.unwrap();
+
+    assert_eq!(engine.take_protocol_errors().len(), MAX_PROTOCOL_ERRORS);
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn handshake_skips_banner() {
+    let engine = crate::mock::MockEngine::new()
+        .name("Banner Engine")
+        .banner("Banner Engine 1.0 by nobody")
+        .banner("")
+        .banner("type 'help' for a list of commands")
+        .spawn();
+
+    let mut erx = engine.etx.subscribe();
+
+    let handshake = engine.handshake(HandshakeConfig::new()).await.unwrap();
+
+    match erx.recv().await.unwrap() {
+        UciEvent::Banner(line) => assert_eq!(line, "Banner Engine 1.0 by nobody"),
+        event => panic!("unexpected event {:?}", event),
+    }
+
+    assert_eq!(
+        handshake.banner,
+        vec![
+            "Banner Engine 1.0 by nobody".to_string(),
+            "type 'help' for a list of commands".to_string()
+        ]
+    );
+    assert_eq!(handshake.lines, vec!["id name Banner Engine".to_string()]);
+    assert_eq!(
+        engine.engine_id().unwrap().name,
+        Some("Banner Engine".to_string())
+    );
+
+    let go_result = engine.go(GoJob::new().pos_startpos()).await.unwrap();
+
+    assert_eq!(go_result.bestmove, Some("0000".to_string()));
+
+    engine.shutdown().await;
+}
+
+#[test]
+fn engine_id_version() {
+    let id = |name: &str| EngineId {
+        name: Some(name.to_string()),
+        author: None,
+    };
+
+    assert_eq!(id("Stockfish 16.1").version(), Some("16.1".to_string()));
+    assert_eq!(id("Lc0 v0.30.0").version(), Some("0.30.0".to_string()));
+    assert_eq!(
+        id("Komodo Dragon 3.2 64-bit").version(),
+        Some("3.2".to_string())
+    );
+    assert_eq!(id("Stockfish dev").version(), None);
+    assert_eq!(EngineId::default().version(), None);
+}
+
+#[tokio::test]
+async fn handshake_options() {
+    let engine = crate::mock::MockEngine::new()
+        .option("name Hash type spin default 16 min 1 max 1024")
+        .option("name Ponder type check default false")
+        .option("name broken")
+        .spawn();
+
+    assert_eq!(engine.options(), vec![]);
+
+    engine.handshake(HandshakeConfig::new()).await.unwrap();
+
+    let options = engine.options();
+
+    assert_eq!(options.len(), 2);
+    assert_eq!(options[0].name, "Hash".to_string());
+    assert_eq!(
+        options[1].kind,
+        UciOptionKind::Check {
+            default: Some(false)
+        }
+    );
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn handshake_registration() {
+    let engine = crate::mock::MockEngine::new()
+        .require_registration("1234")
+        .spawn();
+
+    // without credentials register later is sent and the engine stays unregistered
+    let handshake = engine.handshake(HandshakeConfig::new()).await.unwrap();
+
+    assert_eq!(handshake.registration, Some(CheckStatus::Error));
+    assert_eq!(handshake.copyprotection, None);
+
+    assert_eq!(
+        engine.register_name_code("me", "0000").await,
+        Some(CheckStatus::Error)
+    );
+    assert_eq!(
+        engine.register_name_code("me", "1234").await,
+        Some(CheckStatus::Ok)
+    );
+
+    engine.shutdown().await;
+
+    let engine = crate::mock::MockEngine::new()
+        .require_registration("1234")
+        .spawn();
+
+    let handshake = engine
+        .handshake(HandshakeConfig::new().register("me", "1234"))
+        .await
+        .unwrap();
+
+    assert_eq!(handshake.registration, Some(CheckStatus::Ok));
+    assert_eq!(engine.registration(), Some(CheckStatus::Ok));
+
+    engine.shutdown().await;
+
+    // an engine not requiring registration does not answer, no status is reported
+    let engine = crate::mock::MockEngine::new().spawn();
+
+    engine.handshake(HandshakeConfig::new()).await.unwrap();
+
+    assert_eq!(engine.register_name_code("me", "1234").await, None);
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn handshake_banner_limit() {
+    let engine = crate::mock::MockEngine::new()
+        .banner("line 1")
+        .banner("line 2")
+        .spawn();
+
+    let handshake_result = engine
+        .handshake(HandshakeConfig::new().banner_limit(1))
+        .await;
+
+    assert_eq!(handshake_result, Err(HandshakeError::BannerLimit(1)));
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn handshake_without_uciok() {
+    let engine = crate::mock::MockEngine::new().skip_uciok().spawn();
+
+    let config = HandshakeConfig::new().timeout(Duration::from_millis(50));
+
+    let handshake_result = engine.handshake(config.clone()).await;
+
+    assert_eq!(
+        handshake_result,
+        Err(HandshakeError::Timeout(Duration::from_millis(50)))
+    );
+
+    let handshake = engine.handshake(config.strict(false)).await.unwrap();
+
+    assert!(handshake.degraded);
+    assert_eq!(handshake.lines, vec!["id name MockEngine".to_string()]);
+
+    let go_result = engine.go(GoJob::new().pos_startpos()).await.unwrap();
+
+    assert_eq!(go_result.bestmove, Some("0000".to_string()));
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn fragmented_output() {
+    let (engine_side, remote_side) = tokio::io::duplex(1024);
+
+    let (reader, writer) = tokio::io::split(engine_side);
+
+    let engine = UciEngine::new_with_io(reader, writer);
+
+    let mut arx = engine.atx.subscribe();
+
+    tokio::spawn(async move {
+        let (remote_reader, mut remote_writer) = tokio::io::split(remote_side);
+
+        let mut lines = BufReader::new(remote_reader).lines();
+
+        while let Ok(Some(line)) = lines.next_line().await {
+            if line == "go" {
+                break;
+            }
+        }
+
+        // lines split and batched arbitrarily, with crlf line endings
+        for chunk in [
+            "info de",
+            "pth 1 pv e2e4\r\ninfo depth 2 pv d2d4\r\nbest",
+            "move d2d4\r\n",
+        ] {
+            remote_writer.write_all(chunk.as_bytes()).await.unwrap();
+
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        // keep the connection open until the engine quits
+        while let Ok(Some(line)) = lines.next_line().await {
+            if line == "quit" {
+                break;
+            }
+        }
+    });
+
+    let go_result = engine.go(GoJob::new().pos_startpos()).await.unwrap();
+
+    assert_eq!(go_result.bestmove, Some("d2d4".to_string()));
+
+    let mut depths = vec![];
+
+    while let Ok(ai) = arx.try_recv() {
+        depths.push(ai.depth);
+    }
+
+    assert_eq!(depths, vec![1, 2, 2]);
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn explore_line() {
+    let engine = crate::mock::MockEngine::new()
+        .on_go(vec![
+            crate::mock::MockStep::line("info depth 10 score cp 30 pv d2d4"),
+            crate::mock::MockStep::line("bestmove d2d4"),
+        ])
+        .on_go(vec![
+            crate::mock::MockStep::line("info depth 10 score cp -25 pv c7c5"),
+            crate::mock::MockStep::line("bestmove c7c5"),
+        ])
+        .on_go(vec![
+            crate::mock::MockStep::line("info depth 10 score cp 120 pv g1f3"),
+            crate::mock::MockStep::line("bestmove g1f3"),
+        ])
+        .spawn();
+
+    engine.set_white_pov(true);
+
+    let job = GoJob::new().pos_startpos().go_opt("depth", 10);
+
+    let exploration = engine.explore(job, &["e2e4", "f7f6"], 50).await.unwrap();
+
+    assert_eq!(exploration.steps.len(), 2);
+    assert_eq!(exploration.steps[0].best_score, Score::Cp(30));
+    assert_eq!(exploration.steps[0].score, Score::Cp(25));
+    assert_eq!(exploration.steps[0].loss_cp, 5);
+    assert_eq!(exploration.steps[1].bestmove, Some("c7c5".to_string()));
+    assert_eq!(exploration.steps[1].score, Score::Cp(-120));
+    assert_eq!(exploration.steps[1].loss_cp, 95);
+    assert_eq!(exploration.wrong_at, Some(1));
+
+    assert!(engine.explore(GoJob::new(), &[], 50).await.is_none());
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn verified_search() {
+    let engine = crate::mock::MockEngine::new()
+        .on_go(vec![
+            crate::mock::MockStep::line("info depth 8 score cp 150 pv e2e4 e7e5"),
+            crate::mock::MockStep::line("bestmove e2e4"),
+        ])
+        .on_go(vec![
+            crate::mock::MockStep::line("info depth 8 score cp -120 pv e7e5"),
+            crate::mock::MockStep::line("bestmove e7e5"),
+        ])
+        .on_go(vec![
+            crate::mock::MockStep::line("info depth 8 score cp 150 pv d1h5 g6h5"),
+            crate::mock::MockStep::line("bestmove d1h5"),
+        ])
+        .on_go(vec![
+            crate::mock::MockStep::line("info depth 8 score cp 700 pv g6h5"),
+            crate::mock::MockStep::line("bestmove g6h5"),
+        ])
+        .on_go(vec![
+            crate::mock::MockStep::line("info depth 8 score cp 150 pv e2e4"),
+            crate::mock::MockStep::line("bestmove e2e4"),
+        ])
+        .spawn();
+
+    let job = || GoJob::new().pos_startpos().go_opt("depth", 8);
+
+    let go_result = engine.go_verified(job(), 50).await.unwrap();
+
+    assert_eq!(go_result.bestmove, Some("e2e4".to_string()));
+    assert!(go_result.verified);
+
+    // the score collapses one ply deeper
+    let go_result = engine.go_verified(job(), 50).await.unwrap();
+
+    assert_eq!(go_result.bestmove, Some("d1h5".to_string()));
+    assert!(!go_result.verified);
+
+    // without position there is nothing to verify
+    let go_result = engine
+        .go_verified(GoJob::new().go_opt("depth", 8), 50)
+        .await
+        .unwrap();
+
+    assert!(!go_result.verified);
+
+    engine.shutdown().await;
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_go_job() {
+    let go_job = GoJob::new()
+        .uci_opt("Hash", 128)
+        .pos_fen("k7/8/8/8/8/8/R7/7K w - - 0 1")
+        .pos_moves("h1h2")
+        .go_opt("depth", 12);
+
+    let json = serde_json::to_string(&go_job).unwrap();
+
+    let decoded: GoJob = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(decoded.to_commands(), go_job.to_commands());
+}
+
+#[test]
+fn position_push_move() {
+    let go_job = GoJob::new()
+        .pos_startpos()
+        .pos_moves("e2e4")
+        .go_opt("depth", 5)
+        .position()
+        .unwrap()
+        .push_move("e7e5");
+
+    assert_eq!(
+        go_job.to_commands(),
+        vec!["position startpos moves e2e4 e7e5", "go"]
+    );
+
+    assert!(GoJob::new().position().is_none());
+}
+
+#[tokio::test]
+async fn set_aliased_option() {
+    let engine = crate::mock::MockEngine::new()
+        .option("name TbPath type string default <empty>")
+        .spawn();
+
+    engine.handshake(HandshakeConfig::default()).await.unwrap();
+
+    let aliases = OptionAliases::new();
+
+    assert_eq!(
+        engine.set_option(&aliases, "SyzygyPath", "/tb"),
+        Some("TbPath".to_string())
+    );
+    assert_eq!(engine.set_option(&aliases, "Hash", 128), None);
+
+    engine.shutdown().await;
+}
+
+#[test]
+fn duration_builders() {
+    let commands = GoJob::new()
+        .pos_startpos()
+        .movetime(Duration::from_millis(1500))
+        .move_overhead_duration(Duration::from_micros(200_900))
+        .to_commands();
+
+    assert_eq!(commands.last().unwrap(), "go movetime 1300");
+
+    let tc = Timecontrol::from_durations(
+        Duration::from_secs(180),
+        Duration::from_secs(2),
+        Duration::from_millis(175_500),
+        Duration::from_secs(2),
+    );
+
+    assert_eq!(
+        (tc.wtime, tc.winc, tc.btime, tc.binc),
+        (180000, 2000, 175500, 2000)
+    );
+    assert_eq!(tc.btime_duration(), Duration::from_millis(175_500));
+}
+
+#[test]
+fn side_to_move() {
+    assert_eq!(
+        GoJob::new().pos_startpos().side_to_move(),
+        Some(Color::White)
+    );
+    assert_eq!(
+        GoJob::new().pos_startpos().pos_moves("e2e4").side_to_move(),
+        Some(Color::Black)
+    );
+    assert_eq!(
+        GoJob::new()
+            .pos_fen("k7/8/8/8/8/8/R7/7K b - - 0 1")
+            .pos_moves("a8b8 h1g1")
+            .side_to_move(),
+        Some(Color::Black)
+    );
+    assert_eq!(GoJob::new().side_to_move(), None);
+}
+
+#[test]
+fn phase_depth() {
+    let classifier = PhaseClassifier::new()
+        .depth(Phase::Middlegame, 20)
+        .depth(Phase::Endgame, 30);
+
+    let opening = GoJob::new().pos_startpos().pos_moves("e2e4 e7e5");
+
+    assert_eq!(opening.phase(&classifier), Some(Phase::Opening));
+    assert_eq!(
+        opening.phase_depth(&classifier).go_options.get("depth"),
+        None
+    );
+
+    let endgame = GoJob::new()
+        .pos_fen("k7/8/8/8/8/8/R7/7K w - - 0 1")
+        .phase_depth(&classifier);
+
+    assert_eq!(endgame.go_options.get("depth"), Some(&"30".to_string()));
+
+    let fixed = GoJob::new()
+        .pos_fen("k7/8/8/8/8/8/R7/7K w - - 0 1")
+        .go_opt("depth", 12)
+        .phase_depth(&classifier);
+
+    assert_eq!(fixed.go_options.get("depth"), Some(&"12".to_string()));
+    assert_eq!(GoJob::new().phase(&classifier), None);
+
+    // the middlegame is reached by playing moves
+    let moves = "e2e4 e7e5 g1f3 b8c6 f1b5 a7a6 b5a4 g8f6 e1g1 f8e7 \
+                 f1e1 b7b5 a4b3 d7d6 c2c3 e8g8 h2h3 c6a5 b3c2 c7c5";
+
+    let middlegame = GoJob::new()
+        .pos_startpos()
+        .pos_moves(moves)
+        .phase_depth(&classifier);
+
+    assert_eq!(middlegame.phase(&classifier), Some(Phase::Middlegame));
+    assert_eq!(middlegame.go_options.get("depth"), Some(&"20".to_string()));
+
+    // captures in the moves are replayed
+    let endgame = GoJob::new()
+        .pos_fen("q5rk/8/8/8/8/8/8/Q3R2K w - - 0 1")
+        .pos_moves("a1a8")
+        .phase_depth(&classifier);
+
+    assert_eq!(endgame.go_options.get("depth"), Some(&"30".to_string()));
+
+    // moves that can not be replayed leave the phase unknown
+    let unknown = GoJob::new()
+        .pos_startpos()
+        .pos_moves("e3e4")
+        .phase_depth(&classifier);
+
+    assert_eq!(unknown.phase(&classifier), None);
+    assert_eq!(unknown.go_options.get("depth"), None);
+}
+
+#[tokio::test]
+async fn white_pov_scores() {
+    let engine = crate::mock::MockEngine::new()
+        .on_go(vec![
+            crate::mock::MockStep::line("info depth 10 score cp 40 pv e7e5"),
+            crate::mock::MockStep::line("bestmove e7e5"),
+        ])
+        .on_go(vec![
+            crate::mock::MockStep::line("info depth 10 score cp 40 pv e7e5"),
+            crate::mock::MockStep::line("bestmove e7e5"),
+        ])
+        .on_go(vec![
+            crate::mock::MockStep::line("info depth 10 score cp 40 pv e7e5"),
+            crate::mock::MockStep::line("bestmove e7e5"),
+        ])
+        .on_go(vec![
+            crate::mock::MockStep::line("info depth 10 score cp 50 pv g1f3"),
+            crate::mock::MockStep::line("bestmove g1f3"),
+        ])
+        .spawn();
+
+    let job = || GoJob::new().pos_startpos().pos_moves("e2e4");
+
+    let go_result = engine.go(job()).await.unwrap();
+
+    assert_eq!(go_result.ai.score, Score::Cp(40));
+
+    engine.set_white_pov(true);
+
+    let mut arx = engine.atx.subscribe();
+
+    let go_result = engine.go(job()).await.unwrap();
+
+    assert_eq!(go_result.ai.score, Score::Cp(-40));
+    assert_eq!(arx.recv().await.unwrap().score, Score::Cp(-40));
+    assert_eq!(engine.get_ai().score, Score::Cp(-40));
+
+    // comparisons still see the side to move, black drops from +0.40 to -0.50
+    let go_result = engine.go_verified(job(), 50).await.unwrap();
+
+    assert_eq!(go_result.ai.score, Score::Cp(-40));
+    assert!(!go_result.verified);
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn search_suspects() {
+    let engine = crate::mock::MockEngine::new()
+        .on_go(vec![
+            crate::mock::MockStep::line("info depth 300 score cp 20 pv e2e4"),
+            crate::mock::MockStep::line("info currmove e2e4 currmovenumber 1"),
+            crate::mock::MockStep::line("bestmove e2e4"),
+        ])
+        .on_go(vec![crate::mock::MockStep::line("bestmove e2e4")])
+        .spawn();
+
+    let go_result = engine.go(GoJob::new().pos_startpos()).await.unwrap();
+
+    assert_eq!(go_result.suspects, vec![SuspectField::Depth(300)]);
+    assert_eq!(engine.suspects(), go_result.suspects);
+
+    let go_result = engine.go(GoJob::new().pos_startpos()).await.unwrap();
+
+    assert!(go_result.suspects.is_empty());
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn retain_history() {
+    let search = || {
+        vec![
+            crate::mock::MockStep::line("info depth 1 score cp 20 pv e7e5"),
+            crate::mock::MockStep::line("info depth 2 currmove e7e5 currmovenumber 1"),
+            crate::mock::MockStep::line("info depth 2 score cp 35 pv c7c5"),
+            crate::mock::MockStep::line("bestmove c7c5"),
+        ]
+    };
+
+    let engine = crate::mock::MockEngine::new()
+        .on_go(search())
+        .on_go(search())
+        .spawn();
+
+    let job = || GoJob::new().pos_startpos().pos_moves("e2e4");
+
+    let go_result = engine.go(job()).await.unwrap();
+
+    assert!(go_result.history.is_empty());
+
+    engine.set_retain_history(true);
+    engine.set_white_pov(true);
+
+    let go_result = engine.go(job()).await.unwrap();
+
+    assert_eq!(
+        go_result.history.scores(),
+        vec![(1, Score::Cp(-20)), (2, Score::Cp(-35))]
+    );
+    assert_eq!(engine.get_history().len(), 2);
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn search_trend() {
+    let engine = crate::mock::MockEngine::new()
+        .on_go(vec![
+            crate::mock::MockStep::line("info depth 1 score cp 20 pv e2e4"),
+            crate::mock::MockStep::line("info depth 2 score cp 25 pv e2e4"),
+            crate::mock::MockStep::line("info depth 3 score cp 60 pv e2e4"),
+            crate::mock::MockStep::line("bestmove e2e4"),
+        ])
+        .spawn();
+
+    let mut erx = engine.etx.subscribe();
+
+    engine.go(GoJob::new().pos_startpos()).await.unwrap();
+
+    let mut trends = vec![];
+
+    while let Ok(event) = erx.try_recv() {
+        if let UciEvent::Trend(trend) = event {
+            trends.push(trend);
+        }
+    }
+
+    assert_eq!(trends, vec![Trend::Stable, Trend::Improving]);
+    assert_eq!(engine.trend(), Some(Trend::Improving));
+
+    // a new search starts over
+    engine.go(GoJob::new().pos_startpos()).await.unwrap();
+
+    assert_eq!(engine.trend(), None);
+
+    engine.shutdown().await;
+}
+
+#[test]
+fn analysis_profile() {
+    let profile = AnalysisProfile::new()
+        .go_opt("depth", 30)
+        .go_opt("movetime", 5000)
+        .uci_opt("MultiPV", 3)
+        .uci_opt("SyzygyPath", "/tb")
+        .uci_opt("Contempt", 0);
+
+    let go_job = profile.apply(GoJob::new().pos_startpos().go_opt("movetime", 100));
+
+    assert_eq!(go_job.go_options.get("depth"), Some(&"30".to_string()));
+    assert_eq!(go_job.go_options.get("movetime"), Some(&"100".to_string()));
+    assert_eq!(go_job.uci_options.get("MultiPV"), Some(&"3".to_string()));
+
+    let declared: Vec<UciOptionDef> = [
+        "option name MultiPV type spin default 1 min 1 max 500",
+        "option name SyzygyPath type string default <empty>",
+    ]
+    .iter()
+    .filter_map(UciOptionDef::parse)
+    .collect();
+
+    assert_eq!(
+        profile.revert_commands(&declared, &HashMap::new(), &go_job.uci_options),
+        vec![
+            "setoption name MultiPV value 1",
+            "setoption name SyzygyPath value <empty>"
+        ]
+    );
+
+    // values sent before take precedence, unchanged values are not reverted
+    let sent: HashMap<String, String> = [("multipv", "3"), ("syzygypath", "/old")]
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+
+    assert_eq!(
+        profile.revert_commands(&declared, &sent, &go_job.uci_options),
+        vec!["setoption name SyzygyPath value /old"]
+    );
+}
+
+#[tokio::test]
+async fn go_profile() {
+    let engine = crate::mock::MockEngine::new()
+        .on_go(vec![crate::mock::MockStep::line("bestmove e2e4")])
+        .spawn();
+
+    engine.add_profile("quick", AnalysisProfile::new().go_opt("depth", 8));
+
+    assert!(engine.go_profile("deep", GoJob::new()).is_none());
+
+    let go_result = engine
+        .go_profile("quick", GoJob::new().pos_startpos())
+        .unwrap()
+        .await
+        .unwrap();
+
+    assert_eq!(go_result.bestmove, Some("e2e4".to_string()));
+
+    engine.shutdown().await;
+
+    let engine = crate::mock::MockEngine::new()
+        .option("name Threads type spin default 1 min 1 max 512")
+        .spawn();
+
+    engine.handshake(HandshakeConfig::new()).await.unwrap();
+
+    engine.add_profile("deep", AnalysisProfile::new().uci_opt("Threads", 8));
+
+    drop(engine.go(GoJob::new().uci_opt("Threads", 4)));
+
+    engine
+        .go_profile("deep", GoJob::new().pos_startpos())
+        .unwrap()
+        .await
+        .unwrap();
+
+    // reverted to the value sent before the profile, not the declared default
+    assert_eq!(
+        engine.sent_options.lock().unwrap().get("threads"),
+        Some(&"4".to_string())
+    );
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn draw_policy() {
+    let engine = crate::mock::MockEngine::new()
+        .option("name Contempt type spin default 24 min -100 max 100")
+        .spawn();
+
+    assert!(engine.set_draw_policy(DrawPolicy::Neutral).is_empty());
+
+    engine.handshake(HandshakeConfig::default()).await.unwrap();
+
+    assert_eq!(
+        engine.set_draw_policy(DrawPolicy::Neutral),
+        vec![("Contempt".to_string(), "0".to_string())]
+    );
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn only_move() {
+    let engine = crate::mock::MockEngine::new()
+        .option("name MultiPV type spin default 1 min 1 max 500")
+        .on_go(vec![
+            crate::mock::MockStep::line("info depth 10 multipv 1 score cp 250 pv e1d1"),
+            crate::mock::MockStep::line("info depth 10 multipv 2 score cp -80 pv e1f1"),
+            crate::mock::MockStep::line("bestmove e1d1"),
+        ])
+        .on_go(vec![
+            crate::mock::MockStep::line("info depth 10 multipv 1 score cp 40 pv e2e4"),
+            crate::mock::MockStep::line("info depth 10 multipv 2 score cp 25 pv d2d4"),
+            crate::mock::MockStep::line("bestmove e2e4"),
+        ])
+        .on_go(vec![
+            crate::mock::MockStep::line("info depth 10 multipv 1 score mate 1 pv h7h8q"),
+            crate::mock::MockStep::line("bestmove h7h8q"),
+        ])
+        .spawn();
+
+    let job = || GoJob::new().pos_startpos().go_opt("depth", 10);
+
+    // MultiPV is not known to be declared before the handshake
+    assert_eq!(engine.is_only_move(job(), 100).await, None);
+
+    engine.handshake(HandshakeConfig::new()).await.unwrap();
+
+    engine.set_option(&OptionAliases::new(), "MultiPV", 3);
+
+    assert_eq!(engine.is_only_move(job(), 100).await, Some(true));
+
+    // the previous value is restored
+    assert_eq!(
+        engine.sent_options.lock().unwrap().get("multipv"),
+        Some(&"3".to_string())
+    );
+
+    assert_eq!(engine.is_only_move(job(), 100).await, Some(false));
+
+    // a single legal move produces a single line
+    assert_eq!(engine.is_only_move(job(), 100).await, Some(true));
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn ping_measures_latency() {
+    let engine = crate::mock::MockEngine::new().spawn();
+
+    assert!(engine.latency().is_none());
+
+    let latency = engine.ping().await;
+
+    assert!(latency.is_some());
+    assert_eq!(engine.latency(), latency);
+
+    engine.shutdown().await;
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn shutdown_joins_tasks() {
+    // cat exits on end of input, which is what an engine ignoring quit would do
+    let engine = UciEngine::new("cat");
+
+    engine.shutdown().await;
+
+    assert!(engine.tasks.lock().unwrap().is_empty());
 }